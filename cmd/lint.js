@@ -0,0 +1,78 @@
+const Hexo = require("hexo");
+const hexo = new Hexo(process.cwd(), { silent: false });
+const fs = require("node:fs");
+const {
+  logInfo,
+  logWarn,
+  logError,
+  lintMarkdown,
+  fixMarkdown,
+} = require("../rust-lib/index.js");
+const { parseArgs } = require("node:util");
+const { configureHighlighterFromHexo } = require("../lib/highlighter.js");
+const { markdownOptionsFromHexo } = require("../lib/markdownOptions.js");
+
+const { values } = parseArgs({
+  options: {
+    "days-ago": { type: "string", default: "10000" },
+    fix: { type: "boolean", default: false },
+  },
+});
+
+const daysAgo = values["days-ago"];
+const fix = values.fix;
+
+logInfo(`Lint updated in ${daysAgo} days ago contents.`);
+
+function lint(file) {
+  const markdownOptions = markdownOptionsFromHexo(hexo);
+  let source = fs.readFileSync(file, "utf8");
+  if (fix) {
+    const fixed = fixMarkdown(source, markdownOptions);
+    if (fixed !== source) {
+      fs.writeFileSync(file, fixed);
+      logInfo(`${file}: fixed`);
+      source = fixed;
+    }
+  }
+
+  const diagnostics = lintMarkdown(source, markdownOptions);
+  for (const d of diagnostics) {
+    const message = `${file}:${d.line}:${d.column}: ${d.message} (${d.rule})`;
+    if (d.severity === "error") {
+      logError(message);
+    } else {
+      logWarn(message);
+    }
+  }
+  return diagnostics.filter((d) => d.severity === "error").length;
+}
+
+hexo.init().then(() => {
+  configureHighlighterFromHexo(hexo);
+  hexo.load().then(() => {
+    let date = new Date();
+    date = date.setDate(date.getDate() - daysAgo);
+
+    const contents = hexo.locals
+      .get("posts")
+      .filter((c) => c.updated > date)
+      .toArray()
+      .concat(
+        hexo.locals
+          .get("pages")
+          .filter((c) => c.updated > date)
+          .toArray(),
+      )
+      .filter((c) => c.full_source.endsWith(".md"));
+
+    let errors = 0;
+    for (const content of contents) {
+      errors += lint(content.full_source);
+    }
+    if (errors > 0) {
+      logError(`${errors} error(s) found.`);
+      process.exit(1);
+    }
+  });
+});
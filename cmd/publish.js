@@ -3,10 +3,17 @@ const hexo = new Hexo(process.cwd(), { silent: false });
 const { logInfo, logError } = require("../rust-lib/index.js");
 
 const { publish } = require("../lib/contents/publisher.js");
+const {
+  listPostLinks,
+  listContentPaths,
+} = require("../lib/contents/hexoContentResolver.js");
 const { copyContentAssets } = require("../lib/contents/assets.js");
 const { invalidateCache } = require("../lib/requests/invalidateCaches");
 const { getAuthToken } = require("../lib/requests/auth");
 const { parseCommonArgs } = require("../lib/parseCommonArgs");
+const { configureHighlighterFromHexo } = require("../lib/highlighter.js");
+const { sanitizeOptionsFromHexo } = require("../lib/sanitizer.js");
+const { markdownOptionsFromHexo } = require("../lib/markdownOptions.js");
 
 const {
   apiUrl,
@@ -39,7 +46,13 @@ if (!deployAssetsDir) {
     // Nothing todo
   }
 
-  async function processContents(contents, contentType, url) {
+  async function processContents(
+    contents,
+    contentType,
+    url,
+    posts,
+    contentPaths,
+  ) {
     const wait = (ms) => new Promise((r) => setTimeout(r, ms));
     let processedCount = 0;
 
@@ -50,6 +63,10 @@ if (!deployAssetsDir) {
           apiUrl,
           token,
           baseUrl: url,
+          posts,
+          contentPaths,
+          sanitizeOptions: sanitizeOptionsFromHexo(hexo),
+          markdownOptions: markdownOptionsFromHexo(hexo),
         });
         if (data) {
           copyContentAssets(item, {
@@ -70,16 +87,31 @@ if (!deployAssetsDir) {
 
   let cnt = 0;
   hexo.init().then(() => {
+    configureHighlighterFromHexo(hexo);
     hexo.load().then(async () => {
       let date = new Date();
       date = date.setDate(date.getDate() - daysAgo);
       const url = hexo.config.url;
+      const postLinks = listPostLinks(hexo);
+      const contentPaths = listContentPaths(hexo);
 
       const posts = hexo.locals.get("posts").filter((c) => c.updated > date);
-      cnt += await processContents(posts, "article", url);
+      cnt += await processContents(
+        posts,
+        "article",
+        url,
+        postLinks,
+        contentPaths,
+      );
 
       const pages = hexo.locals.get("pages").filter((c) => c.updated > date);
-      cnt += await processContents(pages, "page", url);
+      cnt += await processContents(
+        pages,
+        "page",
+        url,
+        postLinks,
+        contentPaths,
+      );
     });
   });
 })();
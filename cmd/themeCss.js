@@ -0,0 +1,35 @@
+const fs = require("node:fs");
+const {
+  logInfo,
+  logError,
+  highlightThemeCss,
+} = require("../rust-lib/index.js");
+const { parseArgs } = require("node:util");
+
+const { values } = parseArgs({
+  options: {
+    theme: { type: "string", default: "InspiredGitHub" },
+    "dark-theme": { type: "string" },
+    "class-prefix": { type: "string" },
+    output: { type: "string" },
+  },
+});
+
+let css;
+try {
+  css = highlightThemeCss({
+    theme: values.theme,
+    darkTheme: values["dark-theme"],
+    classPrefix: values["class-prefix"],
+  });
+} catch (error) {
+  logError(error.message);
+  process.exit(1);
+}
+
+if (values.output) {
+  fs.writeFileSync(values.output, css);
+  logInfo(`CSS written: ${values.output}`);
+} else {
+  process.stdout.write(css);
+}
@@ -2,13 +2,20 @@ const Hexo = require("hexo");
 const hexo = new Hexo(process.cwd(), { silent: false });
 const { logInfo, logError } = require("../rust-lib/index.js");
 
-const { findByPath } = require("../lib/contents/hexoContentResolver.js");
+const {
+  findByPath,
+  listPostLinks,
+  listContentPaths,
+} = require("../lib/contents/hexoContentResolver.js");
 const { publish } = require("../lib/contents/publisher.js");
 const { copyContentAssets } = require("../lib/contents/assets.js");
 const { invalidateCache } = require("../lib/requests/invalidateCaches.js");
 const { getAuthToken } = require("../lib/requests/auth.js");
 const { waitForApiServerReady } = require("../lib/requests/healthCheck.js");
 const { parseCommonArgs } = require("../lib/parseCommonArgs.js");
+const { configureHighlighterFromHexo } = require("../lib/highlighter.js");
+const { sanitizeOptionsFromHexo } = require("../lib/sanitizer.js");
+const { markdownOptionsFromHexo } = require("../lib/markdownOptions.js");
 
 const {
   apiUrl,
@@ -57,6 +64,10 @@ async function handleFileUpdate(file) {
     apiUrl,
     token,
     baseUrl: hexo.config.url,
+    posts: listPostLinks(hexo),
+    contentPaths: listContentPaths(hexo),
+    sanitizeOptions: sanitizeOptionsFromHexo(hexo),
+    markdownOptions: markdownOptionsFromHexo(hexo),
   });
   if (reloadUrl) {
     fetch(reloadUrl)
@@ -92,6 +103,7 @@ hexo.extend.processor.register("**/*.md", handleFileUpdate);
   }
 
   hexo.init().then(() => {
+    configureHighlighterFromHexo(hexo);
     logInfo("Hexo initialized. Watching for file changes...");
     hexo.watch().then(() => {
       watching = true;
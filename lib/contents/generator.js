@@ -1,19 +1,83 @@
-const codeBlockFormatter = require("../contents/codeBlockFormatter.js");
 const {
   removeTemplateEnginesSyntax,
+  expandTagPlugins,
   generateRobots,
   formatPath,
   externalLink,
-  renderMarkdown,
+  renderMarkdownForPayload,
+  checkMarkdownLinks,
+  sanitizeHtml,
+  logWarn,
 } = require("../../rust-lib/index.js");
 
-function generatePayload(content, contentType, baseUrl) {
+function describeStripped(s) {
+  switch (s.kind) {
+    case "tag":
+      return `<${s.tag}>`;
+    case "url":
+      return `${s.attribute}="${s.url}" of <${s.tag}>`;
+    case "style":
+      return `style property ${s.property} of <${s.tag}>`;
+    default:
+      return `${s.attribute} of <${s.tag}>`;
+  }
+}
+
+function generatePayload(
+  content,
+  contentType,
+  baseUrl,
+  posts = [],
+  contentPaths = null,
+  sanitizeOptions = null,
+  markdownOptions = {},
+) {
   const path = formatPath(content.path, contentType);
   const c = removeTemplateEnginesSyntax(content._content);
 
-  const formattedCodeBlockMarkdown = codeBlockFormatter.format(c);
-  const renderedMarkdown = renderMarkdown(formattedCodeBlockMarkdown);
-  const htmlContent = externalLink(renderedMarkdown, baseUrl);
+  // NOTE: Expand from the original content so that `{% raw %}` blocks are still recognized.
+  const expanded = removeTemplateEnginesSyntax(
+    expandTagPlugins(content._content, { postPath: path, posts }),
+  );
+  // NOTE: Post assets are published under the post path, the same as `asset_img`.
+  const images = content.asset_dir
+    ? { assetDir: content.asset_dir, urlPrefix: path }
+    : undefined;
+  // NOTE: Footnote ids must be unique when several contents are shown on one page.
+  const footnoteOptions = {
+    idPrefix: `${path.split("/").filter(Boolean).join("-")}-`,
+  };
+  const {
+    html: renderedMarkdown,
+    mathErrors,
+    toc,
+  } = renderMarkdownForPayload(expanded, {
+    ...markdownOptions,
+    images,
+    footnoteOptions,
+  });
+  mathErrors.forEach((e) => {
+    logWarn(
+      `${path}: failed to render math \`${e.source}\` at line ${e.line}: ${e.message}`,
+    );
+  });
+  if (contentPaths) {
+    checkMarkdownLinks(
+      expanded,
+      { contentPath: path, knownPaths: contentPaths },
+      markdownOptions,
+    ).forEach((d) => {
+      logWarn(`${path}: broken ${d.kind} link at line ${d.line}: ${d.message}`);
+    });
+  }
+  let htmlContent = externalLink(renderedMarkdown, baseUrl);
+  if (sanitizeOptions) {
+    const sanitized = sanitizeHtml(htmlContent, sanitizeOptions);
+    sanitized.stripped.forEach((s) => {
+      logWarn(`${path}: removed ${describeStripped(s)} from the HTML`);
+    });
+    htmlContent = sanitized.html;
+  }
   const data = {
     contentType: contentType,
     path: path,
@@ -60,6 +124,10 @@ function generatePayload(content, contentType, baseUrl) {
     }
   }
 
+  if (toc.length != 0) {
+    Object.assign(data, { toc: toc });
+  }
+
   if (content.series && content.series.trim().length != 0) {
     Object.assign(data, { series: content.series });
   }
@@ -1,3 +1,5 @@
+const { formatPath } = require("../../rust-lib/index.js");
+
 function findByPath(hexo, filePath) {
   const Post = hexo.model("Post");
   const post = Post.findOne({ source: filePath });
@@ -13,6 +15,31 @@ function findByPath(hexo, filePath) {
   return null;
 }
 
+function listPostLinks(hexo) {
+  return hexo.locals
+    .get("posts")
+    .toArray()
+    .map((p) => ({
+      slug: p.slug,
+      path: formatPath(p.path, "article"),
+      title: p.title || p.slug,
+    }));
+}
+
+function listContentPaths(hexo) {
+  const posts = hexo.locals
+    .get("posts")
+    .toArray()
+    .map((p) => formatPath(p.path, "article"));
+  const pages = hexo.locals
+    .get("pages")
+    .toArray()
+    .map((p) => formatPath(p.path, "page"));
+  return posts.concat(pages);
+}
+
 module.exports = {
   findByPath,
+  listPostLinks,
+  listContentPaths,
 };
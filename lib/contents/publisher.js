@@ -8,11 +8,37 @@ const { postContent } = require("../requests/postContent.js");
 const { SKIP_PATHS } = require("../constants.js");
 
 async function publish(item, options) {
-  const { contentType, apiUrl, token, baseUrl } = options;
+  const {
+    contentType,
+    apiUrl,
+    token,
+    baseUrl,
+    posts,
+    contentPaths,
+    sanitizeOptions,
+    markdownOptions,
+  } = options;
   if (shouldSkipPaths(item.path, SKIP_PATHS)) {
     return null;
   }
-  const payload = generatePayload(item, contentType, baseUrl);
+
+  let payload;
+  try {
+    payload = generatePayload(
+      item,
+      contentType,
+      baseUrl,
+      posts,
+      contentPaths,
+      sanitizeOptions,
+      markdownOptions,
+    );
+  } catch (error) {
+    logError(
+      `${contentType.charAt(0).toUpperCase() + contentType.slice(1)} render failed: ${item.path} - ${error}`,
+    );
+    throw error;
+  }
   if (!payload) {
     return null;
   }
@@ -0,0 +1,27 @@
+const path = require("node:path");
+const { configureHighlighter } = require("../rust-lib/index.js");
+
+// Reads `qualtet_cli.highlight` of the Hexo `_config.yml`:
+//
+// qualtet_cli:
+//   highlight:
+//     syntax_dir: syntaxes
+//     aliases:
+//       tf: hcl
+function configureHighlighterFromHexo(hexo) {
+  const config = hexo.config.qualtet_cli?.highlight;
+  if (!config) {
+    return;
+  }
+  configureHighlighter({
+    syntaxDir: config.syntax_dir
+      ? path.resolve(hexo.base_dir, config.syntax_dir)
+      : undefined,
+    syntaxDump: config.syntax_dump
+      ? path.resolve(hexo.base_dir, config.syntax_dump)
+      : undefined,
+    aliases: config.aliases,
+  });
+}
+
+module.exports = { configureHighlighterFromHexo };
@@ -0,0 +1,21 @@
+// Reads `qualtet_cli.markdown` of the Hexo `_config.yml` into `MarkdownOptions`. Math, embeds,
+// ruby, CJK and the other syntax extensions are off unless they are turned on here:
+//
+// qualtet_cli:
+//   markdown:
+//     math: true
+//     embeds: true
+//     ruby: true
+//     cjk: true
+//     task_lists: true
+function markdownOptionsFromHexo(hexo) {
+  const config = hexo.config.qualtet_cli?.markdown ?? {};
+  return Object.fromEntries(
+    Object.entries(config).map(([key, value]) => [
+      key.replace(/_([a-z])/g, (_, c) => c.toUpperCase()),
+      value,
+    ]),
+  );
+}
+
+module.exports = { markdownOptionsFromHexo };
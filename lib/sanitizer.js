@@ -0,0 +1,24 @@
+// Reads `qualtet_cli.sanitize` of the Hexo `_config.yml`. Published HTML is sanitized only when
+// `enabled` is true, and unset lists fall back to the built-in allowlist:
+//
+// qualtet_cli:
+//   sanitize:
+//     enabled: true
+//     iframe_hosts:
+//       - www.youtube.com
+//       - player.vimeo.com
+function sanitizeOptionsFromHexo(hexo) {
+  const config = hexo.config.qualtet_cli?.sanitize;
+  if (!config?.enabled) {
+    return null;
+  }
+  return {
+    tags: config.tags,
+    genericAttributes: config.generic_attributes,
+    tagAttributes: config.tag_attributes,
+    urlSchemes: config.url_schemes,
+    iframeHosts: config.iframe_hosts,
+  };
+}
+
+module.exports = { sanitizeOptionsFromHexo };
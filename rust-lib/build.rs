@@ -2,4 +2,25 @@ extern crate napi_build;
 
 fn main() {
   napi_build::setup();
+  #[cfg(feature = "bundled-syntaxes")]
+  bundle_syntaxes();
+}
+
+/// Compiles the default syntaxes and `syntaxes/**/*.sublime-syntax` into `$OUT_DIR/syntaxes.packdump`.
+#[cfg(feature = "bundled-syntaxes")]
+fn bundle_syntaxes() {
+  use std::path::{Path, PathBuf};
+  use syntect::parsing::SyntaxSet;
+
+  let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("syntaxes");
+  println!("cargo:rerun-if-changed={}", dir.display());
+
+  let mut builder = SyntaxSet::load_defaults_newlines().into_builder();
+  if dir.is_dir() {
+    builder
+      .add_from_folder(&dir, true)
+      .unwrap_or_else(|e| panic!("{}: {}", dir.display(), e));
+  }
+  let out = PathBuf::from(std::env::var("OUT_DIR").unwrap()).join("syntaxes.packdump");
+  syntect::dumps::dump_to_uncompressed_file(&builder.build(), out).unwrap();
 }
@@ -1,7 +1,28 @@
 /* auto-generated by NAPI-RS */
 /* eslint-disable */
+export declare function checkMarkdownLinks(input: string, links: LinkCheckOptions, options?: MarkdownOptions | undefined | null): Array<LinkDiagnostic>
+
+export declare function configureHighlighter(options: HighlighterOptions): void
+
+export declare function expandTagPlugins(text: string, options: TagPluginOptions): string
+
 export declare function externalLink(data: string, baseUrl: string): string
 
+export declare function fixMarkdown(input: string, options?: MarkdownOptions | undefined | null): string
+
+export interface FootnoteOptions {
+  /** Prepended to footnote ids, e.g. derived from the content path to avoid collisions on one page */
+  idPrefix?: string
+  /** Text of the links back to the references. Default: `↩︎` */
+  backRefSymbol?: string
+  /** Heading rendered at the top of the footnote section */
+  heading?: string
+  /** `aria-label` of the footnote section */
+  label?: string
+  /** Render footnotes as `<aside>` after the block that references them. Default: false */
+  sidenotes?: boolean
+}
+
 export declare function formatPath(path: string, contentType: string): string
 
 export declare function generateRobots(noindex: boolean | undefined | null, contentType: string): string
@@ -12,20 +33,94 @@ export declare function getLogLevel(): string
 
 export declare function highlightCode(code: string, options: HighlightOptions): string
 
+export interface HighlighterOptions {
+  /** Directory searched recursively for `.sublime-syntax` files */
+  syntaxDir?: string
+  /** Syntax set dumped with syntect, used instead of the defaults */
+  syntaxDump?: string
+  /** Language aliases, e.g. `{ tf: "hcl" }` */
+  aliases?: Record<string, string>
+}
+
 export interface HighlightOptions {
   lang?: string
   caption?: string
   firstLine?: number
+  diagramLanguages?: Array<string>
+  classPrefix?: string
+  /** Lines rendered with the `marked` class, e.g. `1,4-6`. Numbers include `firstLine` */
+  markLines?: string
+  /** `table` (default) or `pre`: `<pre><code>` with a `<span class="line" data-line="N">` per line */
+  layout?: string
+  /** Line numbers. Default: true */
+  gutter?: boolean
+  /** Empty `<button class="copy-button">` for a client side script. Default: false */
+  copyButton?: boolean
+  /** `<span class="highlight-lang">` with the language. Default: false */
+  languageLabel?: boolean
 }
 
+export declare function highlightThemeCss(options: ThemeCssOptions): string
+
 export declare function httpDelete(baseUrl: string, path: string, token?: string | undefined | null): Promise<string>
 
 export declare function httpGet(baseUrl: string, path: string, token?: string | undefined | null): Promise<string>
 
 export declare function httpPost(baseUrl: string, path: string, data: string, token?: string | undefined | null): Promise<string>
 
+export interface ImageOptions {
+  /** Directory relative image paths are resolved against, e.g. the post asset folder */
+  assetDir?: string
+  /** Prepended to relative image urls, e.g. the formatted post path */
+  urlPrefix?: string
+  /** Emit `width` and `height` read from the image files. Default: true */
+  dimensions?: boolean
+  /** Widths of pre-generated variants named `{stem}-{width}w.{ext}` */
+  srcsetWidths?: Array<number>
+  /** Wrap images with a title into `<figure>`. Default: true */
+  figures?: boolean
+}
+
 export declare function isValidImage(source: string): boolean
 
+export interface LinkCheckOptions {
+  /** Formatted path of the content being rendered (e.g. `/articles/2024/01/example/`) */
+  contentPath: string
+  /** Paths of every published content, normalized with `formatPath` before comparing */
+  knownPaths: Array<string>
+}
+
+export interface LinkDiagnostic {
+  /** `fragment`, `path` or `footnote` */
+  kind: string
+  line: number
+  target: string
+  message: string
+}
+
+export interface LintDiagnostic {
+  rule: string
+  /** `error` or `warning` */
+  severity: string
+  /** 1-based line, including the front matter */
+  line: number
+  /** 1-based column, counted in characters */
+  column: number
+  message: string
+  fix?: LintFix
+}
+
+export interface LintFix {
+  line: number
+  column: number
+  endLine: number
+  endColumn: number
+  /** Replaces the text between the start and end positions. */
+  text: string
+}
+
+export declare function lintMarkdown(input: string, options?: MarkdownOptions | undefined | null): Array<LintDiagnostic>
+
 export declare function logDebug(message: string): void
 
 export declare function logError(message: string): void
@@ -34,12 +129,145 @@ export declare function logInfo(message: string): void
 
 export declare function logWarn(message: string): void
 
+export interface MarkdownOptions {
+  html?: boolean
+  typographer?: boolean
+  smartquotes?: boolean
+  /** Four characters: double open, double close, single open, single close. e.g. `「」『』` */
+  quotes?: string
+  tables?: boolean
+  strikethrough?: boolean
+  footnotes?: boolean
+  lazyload?: boolean
+  headingAnchors?: boolean
+  blockquoteParagraphs?: boolean
+  admonitions?: boolean
+  /** Opt-in, `$inline$` and `$$display$$` math rendered to MathML */
+  math?: boolean
+  /** Opt-in, bare urls of YouTube, X/Twitter, GitHub gists and SpeakerDeck alone in a paragraph */
+  embeds?: boolean
+  /** Opt-in, `{漢字|かんじ}` and `[漢字]^(かんじ)` */
+  ruby?: boolean
+  /** Opt-in, CJK aware softbreaks and quotes */
+  cjk?: boolean
+  /** Opt-in, `- [ ] todo` */
+  taskLists?: boolean
+  /** Opt-in, `Term` followed by `: Definition` */
+  definitionLists?: boolean
+  /** Opt-in, `*[HTML]: Hyper Text Markup Language` */
+  abbreviations?: boolean
+  /** Opt-in, `:smile:` and emoticons such as `:)` */
+  emoji?: boolean
+  highlightClassPrefix?: string
+  /** See `HighlightOptions.layout` */
+  highlightLayout?: string
+  highlightGutter?: boolean
+  highlightCopyButton?: boolean
+  highlightLanguageLabel?: boolean
+  /** Fence languages rendered as `<pre class="lang">` for a client side renderer, e.g. `["mermaid"]` */
+  diagramLanguages?: Array<string>
+  images?: ImageOptions
+  footnoteOptions?: FootnoteOptions
+}
+
+export interface MathError {
+  line: number
+  source: string
+  message: string
+}
+
+export declare function parseMarkdown(input: string, options?: MarkdownOptions | undefined | null): string
+
+export interface PostLinkTarget {
+  slug: string
+  path: string
+  title: string
+}
+
 export declare function removeTemplateEnginesSyntax(text: string): string
 
-export declare function renderMarkdown(input: string): string
+export interface RenderedMarkdownForPayload {
+  html: string
+  mathErrors: Array<MathError>
+  toc: Array<TocItem>
+}
+
+export interface RenderedMarkdownWithMathErrors {
+  html: string
+  errors: Array<MathError>
+}
+
+export interface RenderedMarkdownWithMeta {
+  html: string
+  plainText: string
+  excerpt: string
+  characterCount: number
+  wordCount: number
+  readingTimeMinutes: number
+}
+
+export interface RenderedMarkdownWithToc {
+  html: string
+  toc: Array<TocItem>
+}
+
+export declare function renderMarkdown(input: string, options?: MarkdownOptions | undefined | null): string
+
+export declare function renderMarkdownForPayload(input: string, options?: MarkdownOptions | undefined | null): RenderedMarkdownForPayload
+
+export declare function renderMarkdownWithMathErrors(input: string, options?: MarkdownOptions | undefined | null): RenderedMarkdownWithMathErrors
+
+export declare function renderMarkdownWithMeta(input: string, options?: MarkdownOptions | undefined | null): RenderedMarkdownWithMeta
+
+export declare function renderMarkdownWithToc(input: string, options?: MarkdownOptions | undefined | null): RenderedMarkdownWithToc
+
+export interface SanitizedHtml {
+  html: string
+  stripped: Array<StrippedHtml>
+}
+
+export declare function sanitizeHtml(html: string, options?: SanitizeOptions | undefined | null): SanitizedHtml
+
+export interface SanitizeOptions {
+  tags?: Array<string>
+  genericAttributes?: Array<string>
+  tagAttributes?: Record<string, Array<string>>
+  urlSchemes?: Array<string>
+  iframeHosts?: Array<string>
+}
 
 export declare function setCredential(serviceName: string, authorName: string, password: string): void
 
 export declare function setLogLevel(level: string): void
 
 export declare function shouldSkipPaths(path: string, skipPaths: Array<string>): boolean
+
+export interface StrippedHtml {
+  /** `tag`, `attribute`, `url` or `style` */
+  kind: string
+  tag: string
+  attribute?: string
+  url?: string
+  /** CSS property removed from a `style` attribute */
+  property?: string
+}
+
+export interface TagPluginOptions {
+  postPath?: string
+  posts?: Array<PostLinkTarget>
+}
+
+export interface ThemeCssOptions {
+  /** Name of a syntect default theme (e.g. `InspiredGitHub`) or path of a `.tmTheme` file */
+  theme: string
+  /** Theme for `prefers-color-scheme: dark`. `theme` is then used for light */
+  darkTheme?: string
+  classPrefix?: string
+}
+
+export interface TocItem {
+  level: number
+  text: string
+  id: string
+  children: Array<TocItem>
+}
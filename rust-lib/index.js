@@ -576,22 +576,34 @@ if (!nativeBinding) {
 }
 
 module.exports = nativeBinding
+module.exports.checkMarkdownLinks = nativeBinding.checkMarkdownLinks
+module.exports.configureHighlighter = nativeBinding.configureHighlighter
+module.exports.expandTagPlugins = nativeBinding.expandTagPlugins
 module.exports.externalLink = nativeBinding.externalLink
+module.exports.fixMarkdown = nativeBinding.fixMarkdown
 module.exports.formatPath = nativeBinding.formatPath
 module.exports.generateRobots = nativeBinding.generateRobots
 module.exports.getCredential = nativeBinding.getCredential
 module.exports.getLogLevel = nativeBinding.getLogLevel
 module.exports.highlightCode = nativeBinding.highlightCode
+module.exports.highlightThemeCss = nativeBinding.highlightThemeCss
 module.exports.httpDelete = nativeBinding.httpDelete
 module.exports.httpGet = nativeBinding.httpGet
 module.exports.httpPost = nativeBinding.httpPost
 module.exports.isValidImage = nativeBinding.isValidImage
+module.exports.lintMarkdown = nativeBinding.lintMarkdown
 module.exports.logDebug = nativeBinding.logDebug
 module.exports.logError = nativeBinding.logError
 module.exports.logInfo = nativeBinding.logInfo
 module.exports.logWarn = nativeBinding.logWarn
+module.exports.parseMarkdown = nativeBinding.parseMarkdown
 module.exports.removeTemplateEnginesSyntax = nativeBinding.removeTemplateEnginesSyntax
 module.exports.renderMarkdown = nativeBinding.renderMarkdown
+module.exports.renderMarkdownForPayload = nativeBinding.renderMarkdownForPayload
+module.exports.renderMarkdownWithMathErrors = nativeBinding.renderMarkdownWithMathErrors
+module.exports.renderMarkdownWithMeta = nativeBinding.renderMarkdownWithMeta
+module.exports.renderMarkdownWithToc = nativeBinding.renderMarkdownWithToc
+module.exports.sanitizeHtml = nativeBinding.sanitizeHtml
 module.exports.setCredential = nativeBinding.setCredential
 module.exports.setLogLevel = nativeBinding.setLogLevel
 module.exports.shouldSkipPaths = nativeBinding.shouldSkipPaths
@@ -1,7 +1,12 @@
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::LazyLock;
+
+use markdown_it::common::utils::escape_html;
+use regex::Regex;
 use serde::{Deserialize, Serialize};
-use syntect::html::{ClassStyle, ClassedHTMLGenerator};
-use syntect::parsing::SyntaxSet;
-use syntect::util::LinesWithEndings;
+use syntect::html::{ClassStyle, line_tokens_to_classed_spans};
+use syntect::parsing::{ParseState, ScopeStack, ScopeStackOp, SyntaxReference, SyntaxSet};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct HighlightOptions {
@@ -11,45 +16,351 @@ pub struct HighlightOptions {
   pub caption: Option<String>,
   #[serde(default = "default_first_line")]
   pub first_line: i32,
+  /// Languages rendered as diagram containers. `None` means `DEFAULT_DIAGRAM_LANGUAGES`.
+  #[serde(default)]
+  pub diagram_languages: Option<Vec<String>>,
+  /// Prefix prepended to every syntax class, e.g. `hl-` renders `<span class="hl-source hl-js">`.
+  #[serde(default)]
+  pub class_prefix: Option<String>,
+  /// Lines rendered with the `marked` class, e.g. `1,4-6` or `{1,4-6}`. Numbers include `first_line`.
+  #[serde(default)]
+  pub mark_lines: Option<String>,
+  #[serde(default)]
+  pub layout: HighlightLayout,
+  /// Line numbers (or diff markers). In the `Pre` layout they are left to CSS, see `HighlightLayout::Pre`.
+  #[serde(default = "default_gutter")]
+  pub gutter: bool,
+  /// Adds an empty `<button class="copy-button">` for a client side script.
+  #[serde(default)]
+  pub copy_button: bool,
+  /// Adds `<span class="highlight-lang">` with the language.
+  #[serde(default)]
+  pub language_label: bool,
 }
 
+/// Markup of a highlighted code block.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HighlightLayout {
+  /// `<table>` with a gutter cell and a code cell.
+  #[default]
+  Table,
+  /// `<pre class="code"><code>` with a `<span class="line" data-line="N">` per line, so copied code has no
+  /// line numbers. CSS shows them from `data-line` when `<pre>` has the `line-numbers` class, and `<code>`
+  /// has `data-line-start` when the first line isn't 1.
+  Pre,
+}
+
+/// `// [!code highlight]`, `# [!code hl:3]`, `<!-- [!code highlight] -->` etc. at the end of a line.
+/// `:N` marks N lines starting at the marker line, or after it when the marker is alone on its line.
+static MARK_COMMENT: LazyLock<Regex> = LazyLock::new(|| {
+  Regex::new(
+    r"\s*(?://|#|--|;|/\*|<!--)\s*\[!code (?:highlight|hl)(?::(\d+))?\]\s*(?:\*/|-->)?\s*$",
+  )
+  .unwrap()
+});
+
+/// Languages rendered on the client side (e.g. mermaid.js), so their source is passed through as is.
+pub const DEFAULT_DIAGRAM_LANGUAGES: &[&str] = &["mermaid", "plantuml", "dot", "graphviz"];
+
 fn default_first_line() -> i32 {
   1
 }
 
+fn default_gutter() -> bool {
+  true
+}
+
+/// Other names and fallbacks for languages without a grammar, e.g. when `bundled-syntaxes` is disabled.
 fn normalize_language(lang: &str) -> &str {
   match lang {
     // Perl syntax is most similar to nginx config: block structures ({}), semicolons, and # comments
     "nginx" => "perl",
     "typescript" | "ts" | "tsx" => "javascript",
+    "docker" => "dockerfile",
+    "terraform" => "hcl",
     _ => lang,
   }
 }
 
+/// Grammars and language aliases used in addition to the syntect defaults.
+#[derive(Debug, Clone, Default)]
+pub struct SyntaxConfig {
+  /// Directory searched recursively for `.sublime-syntax` files.
+  pub syntax_dir: Option<PathBuf>,
+  /// Syntax set dumped with `syntect::dumps::dump_to_file`, used instead of the defaults.
+  pub syntax_dump: Option<PathBuf>,
+  /// Language aliases, e.g. `tf` -> `hcl`. Checked before the grammars and the built-in mapping.
+  pub aliases: HashMap<String, String>,
+}
+
+/// Replaces the grammars and aliases used by `highlight`.
+pub fn configure(config: &SyntaxConfig) -> Result<(), String> {
+  syntax_highlighter::configure(config)
+}
+
+/// Stylesheet for the classes rendered by `highlight`.
+#[derive(Debug, Clone, Default)]
+pub struct ThemeCssOptions {
+  /// Name of a syntect default theme (e.g. `InspiredGitHub`) or path of a `.tmTheme` file.
+  pub theme: String,
+  /// Theme for `prefers-color-scheme: dark`. `theme` is then used for `prefers-color-scheme: light`.
+  pub dark_theme: Option<String>,
+  /// Same as `HighlightOptions::class_prefix`.
+  pub class_prefix: Option<String>,
+}
+
+/// Generates CSS for the classed output. The background and foreground of the theme apply to `.highlight`,
+/// and the token rules are scoped to it, e.g. `.highlight .comment`.
+///
+/// # Examples
+///
+/// ```
+/// // theme: InspiredGitHub, dark_theme: base16-ocean.dark
+///
+/// // @media (prefers-color-scheme: light) {
+/// //   .highlight {
+/// //    color: #323232;
+/// //    background-color: #ffffff;
+/// //   }
+/// //   .highlight .comment {
+/// //    color: #969896;
+/// //   font-style: italic;
+/// //   }
+/// //   ...
+/// // }
+/// // @media (prefers-color-scheme: dark) {
+/// //   ...
+/// // }
+/// ```
+pub fn theme_css(options: &ThemeCssOptions) -> Result<String, String> {
+  let css = syntax_highlighter::theme_css(&options.theme, options.class_prefix.as_deref())?;
+  let Some(dark_theme) = &options.dark_theme else {
+    return Ok(css);
+  };
+  let dark_css = syntax_highlighter::theme_css(dark_theme, options.class_prefix.as_deref())?;
+  Ok(format!(
+    "{}\n{}",
+    color_scheme_media("light", &css),
+    color_scheme_media("dark", &dark_css)
+  ))
+}
+
+fn color_scheme_media(scheme: &str, css: &str) -> String {
+  let mut result = format!("@media (prefers-color-scheme: {}) {{\n", scheme);
+  for line in css.lines() {
+    if !line.is_empty() {
+      result.push_str("  ");
+      result.push_str(line);
+    }
+    result.push('\n');
+  }
+  result.push_str("}\n");
+  result
+}
+
 mod syntax_highlighter {
   use super::*;
-  use std::sync::LazyLock;
+  use std::collections::HashSet;
+  use std::path::Path;
+  use std::sync::{Arc, LazyLock, Mutex, RwLock};
+  use syntect::highlighting::{Theme, ThemeSet};
+  use syntect::html::css_for_theme_with_class_style;
 
-  static SYNTAX_SET: LazyLock<SyntaxSet> = LazyLock::new(SyntaxSet::load_defaults_newlines);
+  /// Defaults together with the grammars in `syntaxes/`, compiled by `build.rs`.
+  #[cfg(feature = "bundled-syntaxes")]
+  fn default_syntax_set() -> SyntaxSet {
+    syntect::dumps::from_uncompressed_data(include_bytes!(concat!(
+      env!("OUT_DIR"),
+      "/syntaxes.packdump"
+    )))
+    .expect("bundled syntaxes are broken")
+  }
 
-  pub fn highlight_code_classed(code: &str, lang: &str) -> Result<String, String> {
-    let normalized_lang = normalize_language(lang);
+  #[cfg(not(feature = "bundled-syntaxes"))]
+  fn default_syntax_set() -> SyntaxSet {
+    SyntaxSet::load_defaults_newlines()
+  }
 
-    let syntax = SYNTAX_SET
-      .find_syntax_by_token(normalized_lang)
-      .or_else(|| SYNTAX_SET.find_syntax_by_extension(normalized_lang))
-      .unwrap_or_else(|| SYNTAX_SET.find_syntax_plain_text());
+  pub struct Syntaxes {
+    set: SyntaxSet,
+    aliases: HashMap<String, String>,
+  }
 
-    let mut html_generator =
-      ClassedHTMLGenerator::new_with_class_style(syntax, &SYNTAX_SET, ClassStyle::Spaced);
+  impl Syntaxes {
+    pub fn load(config: &SyntaxConfig) -> Result<Self, String> {
+      let set = match &config.syntax_dump {
+        Some(path) => {
+          syntect::dumps::from_dump_file(path).map_err(|e| format!("{}: {}", path.display(), e))?
+        }
+        None => default_syntax_set(),
+      };
+      let set = match &config.syntax_dir {
+        Some(dir) => {
+          let mut builder = set.into_builder();
+          builder
+            .add_from_folder(dir, true)
+            .map_err(|e| format!("{}: {}", dir.display(), e))?;
+          builder.build()
+        }
+        None => set,
+      };
+      Ok(Self {
+        set,
+        aliases: config.aliases.clone(),
+      })
+    }
 
-    for line in LinesWithEndings::from(code) {
-      html_generator
-        .parse_html_for_line_which_includes_newline(line)
-        .map_err(|e| format!("Syntax highlighting error: {:?}", e))?;
+    fn find_by_name(&self, lang: &str) -> Option<&SyntaxReference> {
+      self
+        .set
+        .find_syntax_by_token(lang)
+        .or_else(|| self.set.find_syntax_by_extension(lang))
     }
 
-    Ok(html_generator.finalize())
+    /// A loaded grammar takes precedence over `normalize_language`, e.g. `ts` uses a TypeScript grammar if there is one.
+    pub fn find(&self, lang: &str) -> Option<&SyntaxReference> {
+      let lang = self.aliases.get(lang).map(String::as_str).unwrap_or(lang);
+      self
+        .find_by_name(lang)
+        .or_else(|| self.find_by_name(normalize_language(lang)))
+    }
+
+    /// Returns the HTML of each line. Scopes that stay open at the end of a line (e.g. `source.rust` or
+    /// a block comment) are closed there and reopened on the next line, so that every line is balanced.
+    pub fn highlight_classed(
+      &self,
+      code: &str,
+      lang: &str,
+      class_prefix: Option<&str>,
+    ) -> Result<Vec<String>, String> {
+      let syntax = self
+        .find(lang)
+        .unwrap_or_else(|| self.set.find_syntax_plain_text());
+      let style = class_style(class_prefix);
+      let error = |e| format!("Syntax highlighting error: {:?}", e);
+
+      let mut parse_state = ParseState::new(syntax);
+      let mut stack = ScopeStack::new();
+      code
+        .split('\n')
+        .map(|line| {
+          let reopen: Vec<(usize, ScopeStackOp)> = stack
+            .scopes
+            .iter()
+            .map(|scope| (0, ScopeStackOp::Push(*scope)))
+            .collect();
+          let (open, _) = line_tokens_to_classed_spans("", &reopen, style, &mut ScopeStack::new())
+            .map_err(error)?;
+
+          let line = format!("{}\n", line);
+          let ops = parse_state
+            .parse_line(&line, &self.set)
+            .map_err(|e| format!("Syntax highlighting error: {:?}", e))?;
+          let (html, _) =
+            line_tokens_to_classed_spans(&line, &ops, style, &mut stack).map_err(error)?;
+
+          Ok(format!(
+            "{}{}{}",
+            open,
+            html.replace('\n', ""),
+            "</span>".repeat(stack.scopes.len())
+          ))
+        })
+        .collect()
+    }
+  }
+
+  static SYNTAXES: LazyLock<RwLock<Arc<Syntaxes>>> = LazyLock::new(|| {
+    RwLock::new(Arc::new(Syntaxes {
+      set: default_syntax_set(),
+      aliases: HashMap::new(),
+    }))
+  });
+  static THEME_SET: LazyLock<ThemeSet> = LazyLock::new(ThemeSet::load_defaults);
+  static CLASS_PREFIXES: LazyLock<Mutex<HashSet<&'static str>>> =
+    LazyLock::new(|| Mutex::new(HashSet::new()));
+
+  /// syntect requires a `&'static str` prefix, so each distinct prefix is leaked once and reused.
+  fn class_style(class_prefix: Option<&str>) -> ClassStyle {
+    let Some(prefix) = class_prefix.filter(|p| !p.is_empty()) else {
+      return ClassStyle::Spaced;
+    };
+    let mut prefixes = CLASS_PREFIXES.lock().unwrap();
+    let prefix = match prefixes.get(prefix) {
+      Some(p) => *p,
+      None => {
+        let p: &'static str = Box::leak(prefix.to_string().into_boxed_str());
+        prefixes.insert(p);
+        p
+      }
+    };
+    ClassStyle::SpacedPrefixed { prefix }
+  }
+
+  /// Loads a default theme by name, or a `.tmTheme` file.
+  fn find_theme(theme: &str) -> Result<Theme, String> {
+    if let Some(theme) = THEME_SET.themes.get(theme) {
+      return Ok(theme.clone());
+    }
+    let path = Path::new(theme);
+    if path.is_file() {
+      return ThemeSet::get_theme(path).map_err(|e| format!("{}: {}", theme, e));
+    }
+    let names: Vec<&str> = THEME_SET.themes.keys().map(String::as_str).collect();
+    Err(format!(
+      "Unknown theme: {} (available: {})",
+      theme,
+      names.join(", ")
+    ))
+  }
+
+  pub fn theme_css(theme: &str, class_prefix: Option<&str>) -> Result<String, String> {
+    let theme = find_theme(theme)?;
+    let css = css_for_theme_with_class_style(&theme, class_style(class_prefix))
+      .map_err(|e| format!("CSS generation error: {:?}", e))?;
+    // the root rule (`.code` or `.<prefix>code`) styles the whole code block, and the others are
+    // scoped to code blocks so that they don't apply to classes of the page, e.g. `.comment`
+    let root = format!(".{}code", class_prefix.unwrap_or_default());
+    let mut result = String::with_capacity(css.len());
+    for line in css.lines() {
+      match line.strip_suffix(" {") {
+        Some(selectors) if selectors == root => result.push_str(".highlight {"),
+        Some(selectors) => {
+          let scoped: Vec<String> = selectors
+            .split(", ")
+            .map(|selector| format!(".highlight {}", selector))
+            .collect();
+          result.push_str(&scoped.join(", "));
+          result.push_str(" {");
+        }
+        None => result.push_str(line),
+      }
+      result.push('\n');
+    }
+    Ok(result)
+  }
+
+  pub fn configure(config: &SyntaxConfig) -> Result<(), String> {
+    let syntaxes = Syntaxes::load(config)?;
+    *SYNTAXES.write().unwrap() = Arc::new(syntaxes);
+    Ok(())
+  }
+
+  fn current() -> Arc<Syntaxes> {
+    SYNTAXES.read().unwrap().clone()
+  }
+
+  pub fn has_syntax(lang: &str) -> bool {
+    current().find(lang).is_some()
+  }
+
+  pub fn highlight_code_classed(
+    code: &str,
+    lang: &str,
+    class_prefix: Option<&str>,
+  ) -> Result<Vec<String>, String> {
+    current().highlight_classed(code, lang, class_prefix)
   }
 }
 
@@ -59,45 +370,199 @@ impl Default for HighlightOptions {
       lang: None,
       caption: None,
       first_line: 1,
+      diagram_languages: None,
+      class_prefix: None,
+      mark_lines: None,
+      layout: HighlightLayout::Table,
+      gutter: true,
+      copy_button: false,
+      language_label: false,
     }
   }
 }
 
+pub fn is_diagram_language(lang: &str, languages: Option<&[String]>) -> bool {
+  match languages {
+    Some(languages) => languages.iter().any(|l| l == lang),
+    None => DEFAULT_DIAGRAM_LANGUAGES.contains(&lang),
+  }
+}
+
+/// Whether `lang` is highlighted or rendered as a diagram. Unknown languages fall back to plain text.
+pub fn is_known_language(lang: &str, diagram_languages: Option<&[String]>) -> bool {
+  let lang = diff_language(lang).unwrap_or(lang);
+  lang.is_empty()
+    || lang == "plaintext"
+    || is_diagram_language(lang, diagram_languages)
+    || syntax_highlighter::has_syntax(lang)
+}
+
+/// Renders a diagram container with the raw (escaped) source, e.g. `<pre class="mermaid">graph TD;</pre>`.
+pub fn render_diagram(code: &str, lang: &str) -> String {
+  format!(
+    "<pre class=\"{}\">{}</pre>",
+    escape_html(lang),
+    escape_html(code)
+  )
+}
+
+/// Parses line ranges such as `1,4-6` or `{1,4-6}`. Invalid parts are ignored.
+pub fn parse_line_ranges(spec: &str) -> Vec<(i32, i32)> {
+  spec
+    .trim()
+    .trim_start_matches('{')
+    .trim_end_matches('}')
+    .split(',')
+    .filter_map(|part| {
+      let part = part.trim();
+      match part.split_once('-') {
+        Some((start, end)) => Some((start.trim().parse().ok()?, end.trim().parse().ok()?)),
+        None => part.parse().ok().map(|n| (n, n)),
+      }
+    })
+    .collect()
+}
+
+/// Removes `[!code highlight]` comments and returns the code with the indexes of the marked lines.
+fn strip_mark_comments(code: &str) -> (String, HashSet<usize>) {
+  let mut lines: Vec<&str> = vec![];
+  let mut marked = HashSet::new();
+  // lines left to mark after a marker alone on its line
+  let mut pending = 0;
+  let total = code.split('\n').count();
+
+  for line in code.split('\n') {
+    let Some(caps) = MARK_COMMENT.captures(line) else {
+      if pending > 0 {
+        marked.insert(lines.len());
+        pending -= 1;
+      }
+      lines.push(line);
+      continue;
+    };
+    let count: usize = caps.get(1).map_or(1, |n| n.as_str().parse().unwrap_or(1));
+    // `N` comes from the document, so it can't mark more lines than there are
+    let count = count.min(total - lines.len());
+    let content = &line[..caps.get(0).unwrap().start()];
+    if content.trim().is_empty() {
+      pending = count;
+    } else {
+      marked.extend(lines.len()..lines.len().saturating_add(count));
+      lines.push(content);
+    }
+  }
+  // removed marker lines are counted in `total`
+  marked.retain(|i| *i < lines.len());
+  (lines.join("\n"), marked)
+}
+
+/// Language of `diff-<lang>`, e.g. `rust` for `diff-rust`.
+fn diff_language(lang: &str) -> Option<&str> {
+  lang.strip_prefix("diff-").filter(|l| !l.is_empty())
+}
+
+/// Removes the leading `+`, `-` or ` ` of each line and returns the code with the marker of each line.
+/// Lines without a marker are unchanged context lines.
+fn strip_diff_markers(code: &str) -> (String, Vec<char>) {
+  let (lines, markers): (Vec<&str>, Vec<char>) = code
+    .split('\n')
+    .map(|line| match line.chars().next() {
+      Some(marker @ ('+' | '-' | ' ')) => (&line[1..], marker),
+      _ => (line, ' '),
+    })
+    .unzip();
+  (lines.join("\n"), markers)
+}
+
+/// Renders code in a table with a line number gutter, or in `<pre><code>`, see `HighlightLayout`.
+///
+/// `diff-<lang>` (e.g. `diff-rust`) highlights the code with `<lang>` after removing the leading `+`/`-`,
+/// marks the lines as `inserted` or `deleted` and shows the markers in the gutter instead of line numbers.
 pub fn highlight(code: &str, options: HighlightOptions) -> String {
   let lang = options.lang.as_deref().unwrap_or("plaintext");
-  let highlighted_html = if lang != "plaintext" && !lang.is_empty() {
-    syntax_highlighter::highlight_code_classed(code, lang).ok()
-  } else {
-    None
+  if is_diagram_language(lang, options.diagram_languages.as_deref()) {
+    return render_diagram(code, lang);
+  }
+
+  let (code, marked_lines) = strip_mark_comments(code);
+  let (code, diff_markers) = match diff_language(lang) {
+    Some(_) => {
+      let (code, markers) = strip_diff_markers(&code);
+      (code, Some(markers))
+    }
+    None => (code, None),
   };
+  let code = code.as_str();
+  let syntax_lang = diff_language(lang).unwrap_or(lang);
+  let mark_ranges = options
+    .mark_lines
+    .as_deref()
+    .map(parse_line_ranges)
+    .unwrap_or_default();
 
-  let lines: Vec<&str> = if let Some(ref html) = highlighted_html {
-    html.split('\n').collect()
+  let highlighted_lines = if syntax_lang != "plaintext" && !syntax_lang.is_empty() {
+    syntax_highlighter::highlight_code_classed(code, syntax_lang, options.class_prefix.as_deref())
+      .ok()
   } else {
-    code.split('\n').collect()
+    None
   };
 
+  // each line is balanced HTML, so that it can be wrapped in its own `<span class="line">`
+  let lines: Vec<String> = highlighted_lines.unwrap_or_else(|| {
+    code
+      .split('\n')
+      .map(|line| {
+        line
+          .replace('&', "&amp;")
+          .replace('<', "&lt;")
+          .replace('>', "&gt;")
+      })
+      .collect()
+  });
+
   let mut numbers = String::new();
   let mut content = String::new();
 
   for (i, line) in lines.iter().enumerate() {
     let line_number = options.first_line + i as i32;
+    let mut class = "line".to_string();
+    let marker = diff_markers.as_ref().and_then(|markers| markers.get(i));
+    match marker {
+      Some('+') => class.push_str(" inserted"),
+      Some('-') => class.push_str(" deleted"),
+      _ => {}
+    }
+    if marked_lines.contains(&i)
+      || mark_ranges
+        .iter()
+        .any(|(start, end)| (*start..=*end).contains(&line_number))
+    {
+      class.push_str(" marked");
+    }
 
-    numbers.push_str(&format!("<span class=\"line\">{}</span><br>", line_number));
-
-    let processed_line = if highlighted_html.is_none() {
-      line
-        .replace('&', "&amp;")
-        .replace('<', "&lt;")
-        .replace('>', "&gt;")
-    } else {
-      line.to_string()
-    };
-
-    content.push_str(&format!(
-      "<span class=\"line\">{}</span><br>",
-      processed_line
-    ));
+    match options.layout {
+      HighlightLayout::Table => {
+        let gutter = match marker {
+          Some(marker) => marker.to_string(),
+          None => line_number.to_string(),
+        };
+        numbers.push_str(&format!("<span class=\"{}\">{}</span><br>", class, gutter));
+        content.push_str(&format!("<span class=\"{}\">{}</span><br>", class, line));
+      }
+      HighlightLayout::Pre => {
+        if i > 0 {
+          content.push('\n');
+        }
+        let marker = match marker {
+          Some(marker) => format!(" data-marker=\"{}\"", marker),
+          None => String::new(),
+        };
+        content.push_str(&format!(
+          "<span class=\"{}\" data-line=\"{}\"{}>{}</span>",
+          class, line_number, marker, line
+        ));
+      }
+    }
   }
 
   let caption_html = if let Some(caption) = &options.caption {
@@ -106,16 +571,66 @@ pub fn highlight(code: &str, options: HighlightOptions) -> String {
     String::new()
   };
 
-  let mut result = format!("<figure class=\"highlight {}\">", lang);
+  // the language comes from the fence info string
+  let mut result = format!("<figure class=\"highlight {}\">", escape_html(lang));
   result.push_str(&caption_html);
-  result.push_str("<table><tr>");
-  result.push_str(&format!("<td class=\"gutter\"><pre>{}</pre></td>", numbers));
-  result.push_str(&format!("<td class=\"code\"><pre>{}</pre></td>", content));
-  result.push_str("</tr></table></figure>");
+  result.push_str(&header(&options));
+  match options.layout {
+    HighlightLayout::Table => {
+      result.push_str("<table><tr>");
+      if options.gutter {
+        result.push_str(&format!("<td class=\"gutter\"><pre>{}</pre></td>", numbers));
+      }
+      result.push_str(&format!("<td class=\"code\"><pre>{}</pre></td>", content));
+      result.push_str("</tr></table>");
+    }
+    HighlightLayout::Pre => {
+      let pre_class = if options.gutter {
+        "code line-numbers"
+      } else {
+        "code"
+      };
+      // an attribute rather than `style="counter-reset: ..."`, which the sanitizer removes
+      let line_start = if options.first_line != 1 {
+        format!(" data-line-start=\"{}\"", options.first_line)
+      } else {
+        String::new()
+      };
+      result.push_str(&format!(
+        "<pre class=\"{}\"><code{}>{}</code></pre>",
+        pre_class, line_start, content
+      ));
+    }
+  }
+  result.push_str("</figure>");
 
   result
 }
 
+/// Language label and copy button placeholder, e.g.
+/// `<div class="highlight-header"><span class="highlight-lang">rust</span><button type="button" class="copy-button" aria-label="Copy"></button></div>`
+fn header(options: &HighlightOptions) -> String {
+  let lang = options.lang.as_deref().filter(|l| !l.is_empty());
+  let label = match lang {
+    Some(lang) if options.language_label => {
+      format!(
+        "<span class=\"highlight-lang\">{}</span>",
+        escape_html(lang)
+      )
+    }
+    _ => String::new(),
+  };
+  let button = if options.copy_button {
+    "<button type=\"button\" class=\"copy-button\" aria-label=\"Copy\"></button>"
+  } else {
+    ""
+  };
+  if label.is_empty() && button.is_empty() {
+    return String::new();
+  }
+  format!("<div class=\"highlight-header\">{}{}</div>", label, button)
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
@@ -250,18 +765,18 @@ mod tests {
   #[test]
   fn test_syntax_highlighter_module() {
     let code = "const x = 42;";
-    let result = syntax_highlighter::highlight_code_classed(code, "javascript");
+    let result = syntax_highlighter::highlight_code_classed(code, "javascript", None);
     assert!(result.is_ok());
-    let html = result.unwrap();
+    let html = result.unwrap().join("\n");
     assert!(html.contains("<span class="));
   }
 
   #[test]
   fn test_syntax_highlighter_plaintext() {
     let code = "plain text";
-    let result = syntax_highlighter::highlight_code_classed(code, "plaintext");
+    let result = syntax_highlighter::highlight_code_classed(code, "plaintext", None);
     assert!(result.is_ok());
-    let html = result.unwrap();
+    let html = result.unwrap().join("\n");
     assert!(html.contains("plain text"));
   }
 
@@ -326,4 +841,560 @@ mod tests {
     assert_eq!(normalize_language("javascript"), "javascript");
     assert_eq!(normalize_language("python"), "python");
   }
+
+  const TEST_SYNTAX: &str = r#"%YAML 1.2
+---
+name: Qualtet Test
+file_extensions: [qtest]
+scope: source.qtest
+contexts:
+  main:
+    - match: '\b(type|interface)\b'
+      scope: keyword.declaration.qtest
+"#;
+
+  fn syntax_dir(name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("qualtet-cli-syntax-{}", name));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("QualtetTest.sublime-syntax"), TEST_SYNTAX).unwrap();
+    dir
+  }
+
+  #[test]
+  fn test_syntax_dir() {
+    let config = SyntaxConfig {
+      syntax_dir: Some(syntax_dir("dir")),
+      ..Default::default()
+    };
+    let syntaxes = syntax_highlighter::Syntaxes::load(&config).unwrap();
+
+    assert_eq!(syntaxes.find("qtest").unwrap().name, "Qualtet Test");
+    assert_eq!(syntaxes.find("qualtet test").unwrap().name, "Qualtet Test");
+    assert_eq!(syntaxes.find("rust").unwrap().name, "Rust");
+    let html = syntaxes
+      .highlight_classed("type A = string;", "qtest", None)
+      .unwrap()
+      .join("\n");
+    assert!(html.contains("<span class=\"keyword declaration qtest\">type</span>"));
+  }
+
+  #[test]
+  fn test_syntax_aliases() {
+    let config = SyntaxConfig {
+      syntax_dir: Some(syntax_dir("aliases")),
+      aliases: HashMap::from([
+        ("qt".to_string(), "qtest".to_string()),
+        ("rs2".to_string(), "rust".to_string()),
+      ]),
+      ..Default::default()
+    };
+    let syntaxes = syntax_highlighter::Syntaxes::load(&config).unwrap();
+
+    assert_eq!(syntaxes.find("qt").unwrap().name, "Qualtet Test");
+    assert_eq!(syntaxes.find("rs2").unwrap().name, "Rust");
+    assert!(syntaxes.find("unknown-lang-xyz").is_none());
+  }
+
+  #[test]
+  #[cfg(feature = "bundled-syntaxes")]
+  fn test_bundled_syntaxes() {
+    let syntaxes = syntax_highlighter::Syntaxes::load(&SyntaxConfig::default()).unwrap();
+    // bundled grammars take precedence over the `normalize_language` fallbacks
+    for (lang, name) in [
+      ("ts", "TypeScript"),
+      ("typescript", "TypeScript"),
+      ("tsx", "TSX"),
+      ("nginx", "Nginx"),
+      ("dockerfile", "Dockerfile"),
+      ("docker", "Dockerfile"),
+      ("tf", "HCL"),
+      ("terraform", "HCL"),
+      ("scala", "Scala"),
+      ("kotlin", "Kotlin"),
+      ("kt", "Kotlin"),
+      ("toml", "TOML"),
+    ] {
+      assert_eq!(syntaxes.find(lang).unwrap().name, name, "{}", lang);
+    }
+
+    for (lang, code, expected) in [
+      (
+        "ts",
+        "interface A { b: string }",
+        "<span class=\"entity name type ts\">A</span>",
+      ),
+      (
+        "tsx",
+        "const a = <div className=\"x\">{b}</div>;",
+        "&lt;/</span><span class=\"entity name tag tsx\">div</span>",
+      ),
+      (
+        "tsx",
+        "useState<string>(\"\");",
+        "<span class=\"support type primitive ts\">string</span>",
+      ),
+      (
+        "nginx",
+        "server {\n  listen 80;\n}",
+        "<span class=\"keyword other directive nginx\">listen</span>",
+      ),
+      (
+        "dockerfile",
+        "FROM rust:1 AS build\nRUN cargo build",
+        "<span class=\"keyword other instruction dockerfile\">RUN</span>",
+      ),
+      (
+        "hcl",
+        "resource \"aws_instance\" \"web\" {\n  ami = \"${var.ami}\"\n}",
+        "<span class=\"storage type block hcl\">resource</span>",
+      ),
+      (
+        "kotlin",
+        "fun main() { println(\"$name\") }",
+        "<span class=\"entity name function kotlin\">main</span>",
+      ),
+      (
+        "toml",
+        "[package]\nname = \"rust-lib\"",
+        "<span class=\"entity name section table toml\">package</span>",
+      ),
+    ] {
+      let html = syntaxes
+        .highlight_classed(code, lang, None)
+        .unwrap()
+        .join("\n");
+      assert!(html.contains(expected), "{}: {}", lang, html);
+    }
+
+    // a comparison isn't a JSX element
+    let html = syntaxes
+      .highlight_classed("if (a < b) {}", "tsx", None)
+      .unwrap()
+      .join("\n");
+    assert!(!html.contains("tag"));
+  }
+
+  #[test]
+  fn test_syntax_config_errors() {
+    let config = SyntaxConfig {
+      syntax_dump: Some(std::env::temp_dir().join("qualtet-cli-syntax-missing.packdump")),
+      ..Default::default()
+    };
+    assert!(syntax_highlighter::Syntaxes::load(&config).is_err());
+  }
+
+  #[test]
+  fn test_theme_css() {
+    let options = ThemeCssOptions {
+      theme: "InspiredGitHub".to_string(),
+      ..Default::default()
+    };
+    let css = theme_css(&options).unwrap();
+    assert!(css.starts_with("/*\n * theme \"GitHub\" generated by syntect\n */\n\n.highlight {\n"));
+    assert!(css.contains(" background-color: #ffffff;\n"));
+    assert!(css.contains("\n.highlight .comment {\n"));
+    assert!(!css.contains(".code {"));
+    // every selector is scoped, including those of rules with several selectors
+    assert!(css.contains(
+      "\n.highlight .constant.character, .highlight .constant.other, .highlight .variable.other.constant {\n"
+    ));
+    for selectors in css.lines().filter_map(|line| line.strip_suffix(" {")) {
+      assert!(
+        selectors.split(", ").all(|s| s.starts_with(".highlight")),
+        "{}",
+        selectors
+      );
+    }
+
+    let options = ThemeCssOptions {
+      class_prefix: Some("hl-".to_string()),
+      ..options
+    };
+    let css = theme_css(&options).unwrap();
+    assert!(css.contains(".highlight {\n"));
+    assert!(css.contains("\n.highlight .hl-comment {\n"));
+  }
+
+  #[test]
+  fn test_theme_css_color_scheme() {
+    let options = ThemeCssOptions {
+      theme: "InspiredGitHub".to_string(),
+      dark_theme: Some("base16-ocean.dark".to_string()),
+      ..Default::default()
+    };
+    let css = theme_css(&options).unwrap();
+    let (light, dark) = css
+      .split_once("@media (prefers-color-scheme: dark) {\n")
+      .unwrap();
+    assert!(light.starts_with("@media (prefers-color-scheme: light) {\n  /*\n"));
+    assert!(
+      light.contains("\n  .highlight {\n   color: #323232;\n   background-color: #ffffff;\n")
+    );
+    assert!(light.ends_with("}\n\n"));
+    assert!(dark.contains("\n  .highlight {\n   color: #c0c5ce;\n   background-color: #2b303b;\n"));
+    assert!(dark.ends_with("}\n"));
+  }
+
+  #[test]
+  fn test_theme_css_tm_theme_file() {
+    let path = std::env::temp_dir().join("qualtet-cli-theme.tmTheme");
+    std::fs::write(
+      &path,
+      r#"<?xml version="1.0" encoding="UTF-8"?>
+<plist version="1.0">
+<dict>
+  <key>name</key><string>Test</string>
+  <key>settings</key>
+  <array>
+    <dict><key>settings</key><dict><key>background</key><string>#101010</string></dict></dict>
+    <dict>
+      <key>scope</key><string>keyword</string>
+      <key>settings</key><dict><key>foreground</key><string>#ff0000</string></dict>
+    </dict>
+  </array>
+</dict>
+</plist>"#,
+    )
+    .unwrap();
+    let options = ThemeCssOptions {
+      theme: path.to_string_lossy().to_string(),
+      ..Default::default()
+    };
+    let css = theme_css(&options).unwrap();
+    assert!(css.contains(".highlight {\n background-color: #101010;\n}"));
+    assert!(css.contains(".highlight .keyword {\n color: #ff0000;\n}"));
+
+    let options = ThemeCssOptions {
+      theme: "no-such-theme".to_string(),
+      ..Default::default()
+    };
+    assert!(theme_css(&options).unwrap_err().contains("InspiredGitHub"));
+  }
+
+  #[test]
+  fn test_is_known_language() {
+    assert!(is_known_language("rust", None));
+    assert!(is_known_language("ts", None));
+    assert!(is_known_language("rs", None));
+    assert!(is_known_language("mermaid", None));
+    assert!(is_known_language("plaintext", None));
+    assert!(!is_known_language("rustt", None));
+    assert!(is_known_language("diff", None));
+    assert!(is_known_language("diff-rust", None));
+    assert!(!is_known_language("diff-rustt", None));
+    assert!(!is_known_language("mermaid", Some(&[])));
+  }
+
+  #[test]
+  fn test_diagram_passthrough() {
+    let code = "graph TD;\n  A-->B;";
+    let options = HighlightOptions {
+      lang: Some("mermaid".to_string()),
+      ..Default::default()
+    };
+
+    let result = highlight(code, options);
+    assert_eq!(
+      result,
+      "<pre class=\"mermaid\">graph TD;\n  A--&gt;B;</pre>"
+    );
+  }
+
+  #[test]
+  fn test_diagram_languages_option() {
+    let options = HighlightOptions {
+      lang: Some("mermaid".to_string()),
+      diagram_languages: Some(vec!["ditaa".to_string()]),
+      ..Default::default()
+    };
+    assert!(highlight("graph TD;", options).contains("<td class=\"gutter\">"));
+
+    let options = HighlightOptions {
+      lang: Some("ditaa".to_string()),
+      diagram_languages: Some(vec!["ditaa".to_string()]),
+      ..Default::default()
+    };
+    assert_eq!(
+      highlight("+--+", options),
+      "<pre class=\"ditaa\">+--+</pre>"
+    );
+  }
+
+  #[test]
+  fn test_class_prefix() {
+    let options = HighlightOptions {
+      lang: Some("javascript".to_string()),
+      class_prefix: Some("hl-".to_string()),
+      ..Default::default()
+    };
+
+    let result = highlight("const x = 42;", options);
+    assert!(result.contains("<span class=\"hl-source hl-js\">"));
+    assert!(!result.contains("<span class=\"source"));
+  }
+
+  #[test]
+  fn test_parse_line_ranges() {
+    assert_eq!(parse_line_ranges("{1,4-6}"), vec![(1, 1), (4, 6)]);
+    assert_eq!(parse_line_ranges(" 2 , x, 3-"), vec![(2, 2)]);
+    assert!(parse_line_ranges("").is_empty());
+  }
+
+  #[test]
+  fn test_no_gutter() {
+    let options = HighlightOptions {
+      lang: Some("plaintext".to_string()),
+      gutter: false,
+      ..Default::default()
+    };
+
+    let result = highlight("a\nb", options);
+    assert_eq!(
+      result,
+      "<figure class=\"highlight plaintext\"><table><tr>\
+       <td class=\"code\"><pre><span class=\"line\">a</span><br><span class=\"line\">b</span><br></pre></td>\
+       </tr></table></figure>"
+    );
+  }
+
+  #[test]
+  fn test_pre_layout_lines_are_children_of_code() {
+    let options = HighlightOptions {
+      lang: Some("rust".to_string()),
+      layout: HighlightLayout::Pre,
+      ..Default::default()
+    };
+    let result = highlight("fn main() {\n    /* a\n    b */\n    x();\n}", options);
+
+    let code = result.split_once("<code>").unwrap().1;
+    let code = code.split_once("</code>").unwrap().0;
+    let lines: Vec<&str> = code.split('\n').collect();
+    assert_eq!(lines.len(), 5);
+    for (i, line) in lines.iter().enumerate() {
+      // a balanced line that starts with its line span is a direct child of `<code>`
+      assert!(
+        line.starts_with(&format!("<span class=\"line\" data-line=\"{}\">", i + 1)),
+        "{}",
+        line
+      );
+      assert_balanced(line);
+    }
+  }
+
+  #[test]
+  fn test_pre_layout() {
+    let options = HighlightOptions {
+      lang: Some("plaintext".to_string()),
+      first_line: 5,
+      mark_lines: Some("6".to_string()),
+      layout: HighlightLayout::Pre,
+      ..Default::default()
+    };
+
+    let result = highlight("a < b\nc", options);
+    assert_eq!(
+      result,
+      "<figure class=\"highlight plaintext\"><pre class=\"code line-numbers\">\
+       <code data-line-start=\"5\"><span class=\"line\" data-line=\"5\">a &lt; b</span>\n\
+       <span class=\"line marked\" data-line=\"6\">c</span></code></pre></figure>"
+    );
+
+    let options = HighlightOptions {
+      lang: Some("rust".to_string()),
+      layout: HighlightLayout::Pre,
+      gutter: false,
+      ..Default::default()
+    };
+    let result = highlight("fn main() {}", options);
+    assert!(result.starts_with(
+      "<figure class=\"highlight rust\"><pre class=\"code\"><code><span class=\"line\" data-line=\"1\"><span class=\"source rust\">"
+    ));
+    assert!(result.ends_with("</span></code></pre></figure>"));
+  }
+
+  #[test]
+  fn test_pre_layout_diff() {
+    let options = HighlightOptions {
+      lang: Some("diff-plaintext".to_string()),
+      layout: HighlightLayout::Pre,
+      ..Default::default()
+    };
+
+    let result = highlight("-a\n+b\n c", options);
+    assert!(result.contains(
+      "<span class=\"line deleted\" data-line=\"1\" data-marker=\"-\">a</span>\n\
+       <span class=\"line inserted\" data-line=\"2\" data-marker=\"+\">b</span>\n\
+       <span class=\"line\" data-line=\"3\" data-marker=\" \">c</span>"
+    ));
+  }
+
+  #[test]
+  fn test_header() {
+    let options = HighlightOptions {
+      lang: Some("rust".to_string()),
+      caption: Some("main.rs".to_string()),
+      copy_button: true,
+      language_label: true,
+      ..Default::default()
+    };
+
+    let result = highlight("fn main() {}", options);
+    assert!(result.starts_with(
+      "<figure class=\"highlight rust\"><figcaption>main.rs</figcaption>\
+       <div class=\"highlight-header\"><span class=\"highlight-lang\">rust</span>\
+       <button type=\"button\" class=\"copy-button\" aria-label=\"Copy\"></button></div><table>"
+    ));
+
+    let options = HighlightOptions {
+      language_label: true,
+      ..Default::default()
+    };
+    assert!(!highlight("a", options).contains("highlight-header"));
+  }
+
+  #[test]
+  fn test_mark_lines() {
+    let options = HighlightOptions {
+      lang: Some("plaintext".to_string()),
+      first_line: 10,
+      mark_lines: Some("{10,12-13}".to_string()),
+      ..Default::default()
+    };
+
+    let result = highlight("a\nb\nc\nd", options);
+    assert!(result.contains(
+      "<pre><span class=\"line marked\">10</span><br><span class=\"line\">11</span><br>\
+       <span class=\"line marked\">12</span><br><span class=\"line marked\">13</span><br></pre>"
+    ));
+    assert!(result.contains(
+      "<pre><span class=\"line marked\">a</span><br><span class=\"line\">b</span><br>\
+       <span class=\"line marked\">c</span><br><span class=\"line marked\">d</span><br></pre>"
+    ));
+  }
+
+  #[test]
+  fn test_mark_line_in_block_comment() {
+    let options = HighlightOptions {
+      lang: Some("rust".to_string()),
+      mark_lines: Some("2".to_string()),
+      ..Default::default()
+    };
+
+    let result = highlight("/* a\n b */\nfn x() {}", options);
+    let lines = code_lines(&result);
+    assert_eq!(lines.len(), 3);
+    for line in &lines {
+      assert_balanced(line);
+    }
+    // the comment is reopened inside the marked line and closed before the next one
+    assert!(lines[1].starts_with(
+      "<span class=\"line marked\"><span class=\"source rust\"><span class=\"comment block rust\">"
+    ));
+    assert!(lines[2].starts_with("<span class=\"line\">"));
+    assert!(!lines[2].contains("comment"));
+  }
+
+  #[test]
+  fn test_mark_comments() {
+    let code = "let a = 1; // [!code highlight]\nlet b = 2;\n# [!code hl:2]\nlet c = 3;\nlet d = 4;\nlet e = 5; <!-- [!code highlight] -->";
+    let (stripped, marked) = strip_mark_comments(code);
+    assert_eq!(
+      stripped,
+      "let a = 1;\nlet b = 2;\nlet c = 3;\nlet d = 4;\nlet e = 5;"
+    );
+    assert_eq!(marked, HashSet::from([0, 2, 3, 4]));
+
+    let options = HighlightOptions {
+      lang: Some("javascript".to_string()),
+      ..Default::default()
+    };
+    let result = highlight("x(); // [!code highlight]\ny();", options);
+    assert!(!result.contains("[!code"));
+    assert!(result.contains("<span class=\"line marked\">1</span>"));
+    assert!(result.contains("<span class=\"line\">2</span>"));
+  }
+
+  #[test]
+  fn test_mark_comments_oversized_count() {
+    for n in ["18446744073709551615", "100000000000"] {
+      let code = format!("a // [!code hl:{}]\nb\n// [!code hl:{}]\nc", n, n);
+      let (code, marked) = strip_mark_comments(&code);
+      assert_eq!(code, "a\nb\nc");
+      assert_eq!(marked, HashSet::from([0, 1, 2]));
+    }
+
+    // not a usize, marks one line
+    let (_, marked) = strip_mark_comments("a // [!code hl:99999999999999999999999]\nb");
+    assert_eq!(marked, HashSet::from([0]));
+  }
+
+  #[test]
+  fn test_diff_language() {
+    let options = HighlightOptions {
+      lang: Some("diff-rust".to_string()),
+      ..Default::default()
+    };
+    let result = highlight(" fn main() {\n-    old();\n+    new();\n }", options);
+
+    assert!(result.starts_with("<figure class=\"highlight diff-rust\">"));
+    assert!(result.contains(
+      "<td class=\"gutter\"><pre><span class=\"line\"> </span><br><span class=\"line deleted\">-</span><br>\
+       <span class=\"line inserted\">+</span><br><span class=\"line\"> </span><br></pre></td>"
+    ));
+    // highlighted with the rust grammar, without the markers
+    assert!(result.contains(
+      "<span class=\"line deleted\"><span class=\"source rust\"><span class=\"meta function rust\">\
+       <span class=\"meta block rust\">    <span class=\"support function rust\">old</span>"
+    ));
+    assert!(result.contains(
+      "<span class=\"line\"><span class=\"source rust\"><span class=\"meta function rust\">"
+    ));
+    assert!(!result.contains("+    new"));
+  }
+
+  /// Each line of the code cell, split at `<br>`.
+  fn code_lines(html: &str) -> Vec<&str> {
+    let code = html.split_once("<td class=\"code\"><pre>").unwrap().1;
+    let code = code.split_once("</pre></td>").unwrap().0;
+    code.split_terminator("<br>").collect()
+  }
+
+  fn assert_balanced(line: &str) {
+    assert_eq!(
+      line.matches("<span").count(),
+      line.matches("</span>").count(),
+      "{}",
+      line
+    );
+    assert!(line.starts_with("<span class=\"line") && line.ends_with("</span>"));
+  }
+
+  #[test]
+  fn test_diff_lines_are_balanced() {
+    let options = HighlightOptions {
+      lang: Some("diff-rust".to_string()),
+      ..Default::default()
+    };
+    // the context line opens a block that the changed lines are in
+    let result = highlight(" fn main() {\n-    old();\n+    new();\n }", options);
+
+    let lines = code_lines(&result);
+    assert_eq!(lines.len(), 4);
+    for line in lines {
+      assert_balanced(line);
+    }
+  }
+
+  #[test]
+  fn test_diff_language_with_marked_lines() {
+    let options = HighlightOptions {
+      lang: Some("diff-plaintext".to_string()),
+      mark_lines: Some("2".to_string()),
+      ..Default::default()
+    };
+    let result = highlight("a\n+b", options);
+    assert!(result.contains(
+      "<span class=\"line\">a</span><br><span class=\"line inserted marked\">b</span><br>"
+    ));
+  }
 }
@@ -184,6 +184,18 @@ pub fn is_valid(source: &str) -> Result<ValidationResult, String> {
   }
 }
 
+/// EXIF orientation of the image at `path`, 1 to 8. `None` when the file has no EXIF data.
+pub fn orientation(path: &Path) -> Option<u32> {
+  let file = File::open(path).ok()?;
+  let exif_data = exif::Reader::new()
+    .read_from_container(&mut BufReader::new(file))
+    .ok()?;
+  exif_data
+    .get_field(exif::Tag::Orientation, exif::In::PRIMARY)?
+    .value
+    .get_uint(0)
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
@@ -6,8 +6,12 @@ mod image_validator;
 mod logger;
 mod markdown;
 mod robots;
+mod sanitizer;
+mod tag_plugin;
 mod utils;
 
+use std::collections::HashMap;
+
 fn init_logger() {
   logger::init();
 }
@@ -37,6 +41,41 @@ pub fn remove_template_engines_syntax(text: String) -> napi::Result<String> {
   Ok(s)
 }
 
+#[napi(object)]
+pub struct PostLinkTarget {
+  pub slug: String,
+  pub path: String,
+  pub title: String,
+}
+
+#[napi(object)]
+pub struct TagPluginOptions {
+  pub post_path: Option<String>,
+  pub posts: Option<Vec<PostLinkTarget>>,
+}
+
+#[napi]
+pub fn expand_tag_plugins(text: String, options: TagPluginOptions) -> napi::Result<String> {
+  let ctx = tag_plugin::TagContext {
+    post_path: options.post_path.unwrap_or_default(),
+    posts: options
+      .posts
+      .unwrap_or_default()
+      .into_iter()
+      .map(|p| {
+        (
+          p.slug,
+          tag_plugin::PostRef {
+            path: p.path,
+            title: p.title,
+          },
+        )
+      })
+      .collect(),
+  };
+  tag_plugin::expand(&text, &ctx).map_err(napi::Error::from_reason)
+}
+
 #[napi]
 pub fn format_path(path: String, content_type: String) -> napi::Result<String> {
   let s = utils::format_path(&path, &content_type);
@@ -63,12 +102,488 @@ pub fn generate_robots(noindex: Option<bool>, content_type: String) -> napi::Res
   Ok(s.to_string())
 }
 
+#[napi(object)]
+pub struct MarkdownOptions {
+  pub html: Option<bool>,
+  pub typographer: Option<bool>,
+  pub smartquotes: Option<bool>,
+  /// Four characters: double open, double close, single open, single close. e.g. `「」『』`
+  pub quotes: Option<String>,
+  pub tables: Option<bool>,
+  pub strikethrough: Option<bool>,
+  pub footnotes: Option<bool>,
+  pub lazyload: Option<bool>,
+  pub heading_anchors: Option<bool>,
+  pub blockquote_paragraphs: Option<bool>,
+  pub admonitions: Option<bool>,
+  /// Opt-in, `$inline$` and `$$display$$` math rendered to MathML
+  pub math: Option<bool>,
+  /// Opt-in, bare urls of YouTube, X/Twitter, GitHub gists and SpeakerDeck alone in a paragraph
+  pub embeds: Option<bool>,
+  /// Opt-in, `{漢字|かんじ}` and `[漢字]^(かんじ)`
+  pub ruby: Option<bool>,
+  /// Opt-in, CJK aware softbreaks and quotes
+  pub cjk: Option<bool>,
+  /// Opt-in, `- [ ] todo`
+  pub task_lists: Option<bool>,
+  /// Opt-in, `Term` followed by `: Definition`
+  pub definition_lists: Option<bool>,
+  /// Opt-in, `*[HTML]: Hyper Text Markup Language`
+  pub abbreviations: Option<bool>,
+  /// Opt-in, `:smile:` and emoticons such as `:)`
+  pub emoji: Option<bool>,
+  pub highlight_class_prefix: Option<String>,
+  /// See `HighlightOptions.layout`
+  pub highlight_layout: Option<String>,
+  pub highlight_gutter: Option<bool>,
+  pub highlight_copy_button: Option<bool>,
+  pub highlight_language_label: Option<bool>,
+  /// Fence languages rendered as `<pre class="lang">` for a client side renderer, e.g. `["mermaid"]`
+  pub diagram_languages: Option<Vec<String>>,
+  pub images: Option<ImageOptions>,
+  pub footnote_options: Option<FootnoteOptions>,
+}
+
+#[napi(object)]
+pub struct ImageOptions {
+  /// Directory relative image paths are resolved against, e.g. the post asset folder
+  pub asset_dir: Option<String>,
+  /// Prepended to relative image urls, e.g. the formatted post path
+  pub url_prefix: Option<String>,
+  /// Emit `width` and `height` read from the image files. Default: true
+  pub dimensions: Option<bool>,
+  /// Widths of pre-generated variants named `{stem}-{width}w.{ext}`
+  pub srcset_widths: Option<Vec<u32>>,
+  /// Wrap images with a title into `<figure>`. Default: true
+  pub figures: Option<bool>,
+}
+
+impl From<Option<ImageOptions>> for markdown::ImageOptions {
+  fn from(options: Option<ImageOptions>) -> Self {
+    let default = markdown::ImageOptions::default();
+    let Some(o) = options else {
+      return default;
+    };
+    Self {
+      asset_dir: o.asset_dir.map(Into::into),
+      url_prefix: o.url_prefix,
+      dimensions: o.dimensions.unwrap_or(default.dimensions),
+      srcset_widths: o.srcset_widths.unwrap_or(default.srcset_widths),
+      figures: o.figures.unwrap_or(default.figures),
+    }
+  }
+}
+
+#[napi(object)]
+pub struct FootnoteOptions {
+  /// Prepended to footnote ids, e.g. derived from the content path to avoid collisions on one page
+  pub id_prefix: Option<String>,
+  /// Text of the links back to the references. Default: `↩︎`
+  pub back_ref_symbol: Option<String>,
+  /// Heading rendered at the top of the footnote section
+  pub heading: Option<String>,
+  /// `aria-label` of the footnote section
+  pub label: Option<String>,
+  /// Render footnotes as `<aside>` after the block that references them. Default: false
+  pub sidenotes: Option<bool>,
+}
+
+impl From<Option<FootnoteOptions>> for markdown::FootnoteOptions {
+  fn from(options: Option<FootnoteOptions>) -> Self {
+    let Some(o) = options else {
+      return Self::default();
+    };
+    Self {
+      id_prefix: o.id_prefix,
+      back_ref_symbol: o.back_ref_symbol,
+      heading: o.heading,
+      label: o.label,
+      sidenotes: o.sidenotes.unwrap_or_default(),
+    }
+  }
+}
+
+impl TryFrom<Option<MarkdownOptions>> for markdown::MarkdownOptions {
+  type Error = napi::Error;
+
+  fn try_from(options: Option<MarkdownOptions>) -> napi::Result<Self> {
+    let default = markdown::MarkdownOptions::default();
+    let Some(o) = options else {
+      return Ok(default);
+    };
+
+    let quotes = match o.quotes {
+      Some(q) => q.chars().collect::<Vec<char>>().try_into().map_err(|_| {
+        napi::Error::from_reason(format!("quotes must be 4 characters, but got `{}`", q))
+      })?,
+      None => default.quotes,
+    };
+
+    Ok(Self {
+      html: o.html.unwrap_or(default.html),
+      typographer: o.typographer.unwrap_or(default.typographer),
+      smartquotes: o.smartquotes.unwrap_or(default.smartquotes),
+      quotes,
+      tables: o.tables.unwrap_or(default.tables),
+      strikethrough: o.strikethrough.unwrap_or(default.strikethrough),
+      footnotes: o.footnotes.unwrap_or(default.footnotes),
+      lazyload: o.lazyload.unwrap_or(default.lazyload),
+      heading_anchors: o.heading_anchors.unwrap_or(default.heading_anchors),
+      blockquote_paragraphs: o
+        .blockquote_paragraphs
+        .unwrap_or(default.blockquote_paragraphs),
+      admonitions: o.admonitions.unwrap_or(default.admonitions),
+      math: o.math.unwrap_or(default.math),
+      embeds: o.embeds.unwrap_or(default.embeds),
+      ruby: o.ruby.unwrap_or(default.ruby),
+      cjk: o.cjk.unwrap_or(default.cjk),
+      task_lists: o.task_lists.unwrap_or(default.task_lists),
+      definition_lists: o.definition_lists.unwrap_or(default.definition_lists),
+      abbreviations: o.abbreviations.unwrap_or(default.abbreviations),
+      emoji: o.emoji.unwrap_or(default.emoji),
+      highlight_class_prefix: o.highlight_class_prefix.or(default.highlight_class_prefix),
+      highlight_layout: highlight_layout(o.highlight_layout)?,
+      highlight_gutter: o.highlight_gutter.unwrap_or(default.highlight_gutter),
+      highlight_copy_button: o
+        .highlight_copy_button
+        .unwrap_or(default.highlight_copy_button),
+      highlight_language_label: o
+        .highlight_language_label
+        .unwrap_or(default.highlight_language_label),
+      diagram_languages: o.diagram_languages.or(default.diagram_languages),
+      images: o.images.into(),
+      footnote_options: o.footnote_options.into(),
+    })
+  }
+}
+
 #[napi]
-pub fn render_markdown(input: String) -> napi::Result<String> {
-  let s = markdown::render(&input);
+pub fn render_markdown(input: String, options: Option<MarkdownOptions>) -> napi::Result<String> {
+  let s = markdown::render(&input, &options.try_into()?);
   Ok(s)
 }
 
+#[napi(object)]
+pub struct TocItem {
+  pub level: u32,
+  pub text: String,
+  pub id: String,
+  pub children: Vec<TocItem>,
+}
+
+impl From<markdown::TocItem> for TocItem {
+  fn from(item: markdown::TocItem) -> Self {
+    Self {
+      level: item.level as u32,
+      text: item.text,
+      id: item.id,
+      children: item.children.into_iter().map(TocItem::from).collect(),
+    }
+  }
+}
+
+#[napi(object)]
+pub struct RenderedMarkdownWithToc {
+  pub html: String,
+  pub toc: Vec<TocItem>,
+}
+
+#[napi]
+pub fn render_markdown_with_toc(
+  input: String,
+  options: Option<MarkdownOptions>,
+) -> napi::Result<RenderedMarkdownWithToc> {
+  let (html, toc) = markdown::render_with_toc(&input, &options.try_into()?);
+  Ok(RenderedMarkdownWithToc {
+    html,
+    toc: toc.into_iter().map(TocItem::from).collect(),
+  })
+}
+
+#[napi(object)]
+pub struct RenderedMarkdownWithMeta {
+  pub html: String,
+  pub plain_text: String,
+  pub excerpt: String,
+  pub character_count: u32,
+  pub word_count: u32,
+  pub reading_time_minutes: u32,
+}
+
+#[napi]
+pub fn render_markdown_with_meta(
+  input: String,
+  options: Option<MarkdownOptions>,
+) -> napi::Result<RenderedMarkdownWithMeta> {
+  let (html, meta) = markdown::render_with_meta(&input, &options.try_into()?);
+  Ok(RenderedMarkdownWithMeta {
+    html,
+    plain_text: meta.plain_text,
+    excerpt: meta.excerpt,
+    character_count: meta.character_count as u32,
+    word_count: meta.word_count as u32,
+    reading_time_minutes: meta.reading_time_minutes as u32,
+  })
+}
+
+#[napi(object)]
+pub struct MathError {
+  pub line: u32,
+  pub source: String,
+  pub message: String,
+}
+
+impl From<markdown::MathError> for MathError {
+  fn from(error: markdown::MathError) -> Self {
+    Self {
+      line: error.line as u32,
+      source: error.source,
+      message: error.message,
+    }
+  }
+}
+
+#[napi(object)]
+pub struct RenderedMarkdownWithMathErrors {
+  pub html: String,
+  pub errors: Vec<MathError>,
+}
+
+#[napi]
+pub fn render_markdown_with_math_errors(
+  input: String,
+  options: Option<MarkdownOptions>,
+) -> napi::Result<RenderedMarkdownWithMathErrors> {
+  let (html, errors) = markdown::render_with_math_errors(&input, &options.try_into()?);
+  Ok(RenderedMarkdownWithMathErrors {
+    html,
+    errors: errors.into_iter().map(MathError::from).collect(),
+  })
+}
+
+#[napi(object)]
+pub struct RenderedMarkdownForPayload {
+  pub html: String,
+  pub math_errors: Vec<MathError>,
+  pub toc: Vec<TocItem>,
+}
+
+/// `renderMarkdownWithMathErrors` and `renderMarkdownWithToc` in one parse, for `generatePayload`.
+#[napi]
+pub fn render_markdown_for_payload(
+  input: String,
+  options: Option<MarkdownOptions>,
+) -> napi::Result<RenderedMarkdownForPayload> {
+  let (html, math_errors, toc) = markdown::render_for_payload(&input, &options.try_into()?);
+  Ok(RenderedMarkdownForPayload {
+    html,
+    math_errors: math_errors.into_iter().map(MathError::from).collect(),
+    toc: toc.into_iter().map(TocItem::from).collect(),
+  })
+}
+
+#[napi(object)]
+pub struct LinkCheckOptions {
+  /// Formatted path of the content being rendered (e.g. `/articles/2024/01/example/`)
+  pub content_path: String,
+  /// Paths of every published content, normalized with `formatPath` before comparing
+  pub known_paths: Vec<String>,
+}
+
+#[napi(object)]
+pub struct LinkDiagnostic {
+  /// `fragment`, `path` or `footnote`
+  pub kind: String,
+  pub line: u32,
+  pub target: String,
+  pub message: String,
+}
+
+impl From<markdown::LinkDiagnostic> for LinkDiagnostic {
+  fn from(diagnostic: markdown::LinkDiagnostic) -> Self {
+    let kind = match diagnostic.kind {
+      markdown::BrokenLinkKind::Fragment => "fragment",
+      markdown::BrokenLinkKind::Path => "path",
+      markdown::BrokenLinkKind::Footnote => "footnote",
+    };
+    Self {
+      kind: kind.to_string(),
+      line: diagnostic.line as u32,
+      target: diagnostic.target,
+      message: diagnostic.message,
+    }
+  }
+}
+
+#[napi]
+pub fn check_markdown_links(
+  input: String,
+  links: LinkCheckOptions,
+  options: Option<MarkdownOptions>,
+) -> napi::Result<Vec<LinkDiagnostic>> {
+  let links = markdown::LinkCheckOptions {
+    content_path: links.content_path,
+    known_paths: links.known_paths,
+  };
+  let diagnostics = markdown::check_links(&input, &options.try_into()?, &links);
+  Ok(diagnostics.into_iter().map(LinkDiagnostic::from).collect())
+}
+
+#[napi(object)]
+pub struct LintFix {
+  pub line: u32,
+  pub column: u32,
+  pub end_line: u32,
+  pub end_column: u32,
+  /// Replaces the text between the start and end positions.
+  pub text: String,
+}
+
+#[napi(object)]
+pub struct LintDiagnostic {
+  pub rule: String,
+  /// `error` or `warning`
+  pub severity: String,
+  /// 1-based line, including the front matter
+  pub line: u32,
+  /// 1-based column, counted in characters
+  pub column: u32,
+  pub message: String,
+  pub fix: Option<LintFix>,
+}
+
+impl From<markdown::LintDiagnostic> for LintDiagnostic {
+  fn from(diagnostic: markdown::LintDiagnostic) -> Self {
+    let severity = match diagnostic.severity {
+      markdown::Severity::Error => "error",
+      markdown::Severity::Warning => "warning",
+    };
+    Self {
+      rule: diagnostic.rule.to_string(),
+      severity: severity.to_string(),
+      line: diagnostic.line as u32,
+      column: diagnostic.column as u32,
+      message: diagnostic.message,
+      fix: diagnostic.fix.map(|fix: markdown::LintFix| LintFix {
+        line: fix.line as u32,
+        column: fix.column as u32,
+        end_line: fix.end_line as u32,
+        end_column: fix.end_column as u32,
+        text: fix.text,
+      }),
+    }
+  }
+}
+
+#[napi]
+pub fn lint_markdown(
+  input: String,
+  options: Option<MarkdownOptions>,
+) -> napi::Result<Vec<LintDiagnostic>> {
+  let diagnostics = markdown::lint(&input, &options.try_into()?);
+  Ok(diagnostics.into_iter().map(LintDiagnostic::from).collect())
+}
+
+/// Returns the markdown with the fixes of `lintMarkdown` applied.
+#[napi]
+pub fn fix_markdown(input: String, options: Option<MarkdownOptions>) -> napi::Result<String> {
+  Ok(markdown::fix(&input, &options.try_into()?))
+}
+
+/// Returns the markdown AST as JSON.
+/// Each node has `type`, `attrs`, `lines` (1-based first and last line) and `children`.
+#[napi]
+pub fn parse_markdown(input: String, options: Option<MarkdownOptions>) -> napi::Result<String> {
+  let ast = markdown::ast(&input, &options.try_into()?);
+  serde_json::to_string(&ast).map_err(|e| napi::Error::from_reason(e.to_string()))
+}
+
+#[napi(object)]
+pub struct SanitizeOptions {
+  pub tags: Option<Vec<String>>,
+  pub generic_attributes: Option<Vec<String>>,
+  pub tag_attributes: Option<HashMap<String, Vec<String>>>,
+  pub url_schemes: Option<Vec<String>>,
+  pub iframe_hosts: Option<Vec<String>>,
+}
+
+#[napi(object)]
+pub struct StrippedHtml {
+  /// `tag`, `attribute`, `url` or `style`
+  pub kind: String,
+  pub tag: String,
+  pub attribute: Option<String>,
+  pub url: Option<String>,
+  /// CSS property removed from a `style` attribute
+  pub property: Option<String>,
+}
+
+impl From<sanitizer::Stripped> for StrippedHtml {
+  fn from(stripped: sanitizer::Stripped) -> Self {
+    match stripped {
+      sanitizer::Stripped::Tag { tag } => Self {
+        kind: "tag".to_string(),
+        tag,
+        attribute: None,
+        url: None,
+        property: None,
+      },
+      sanitizer::Stripped::Attribute { tag, attribute } => Self {
+        kind: "attribute".to_string(),
+        tag,
+        attribute: Some(attribute),
+        url: None,
+        property: None,
+      },
+      sanitizer::Stripped::Url {
+        tag,
+        attribute,
+        url,
+      } => Self {
+        kind: "url".to_string(),
+        tag,
+        attribute: Some(attribute),
+        url: Some(url),
+        property: None,
+      },
+      sanitizer::Stripped::Style { tag, property } => Self {
+        kind: "style".to_string(),
+        tag,
+        attribute: Some("style".to_string()),
+        url: None,
+        property: Some(property),
+      },
+    }
+  }
+}
+
+#[napi(object)]
+pub struct SanitizedHtml {
+  pub html: String,
+  pub stripped: Vec<StrippedHtml>,
+}
+
+#[napi]
+pub fn sanitize_html(
+  html: String,
+  options: Option<SanitizeOptions>,
+) -> napi::Result<SanitizedHtml> {
+  let options = options
+    .map(|o| sanitizer::SanitizeOptions {
+      tags: o.tags,
+      generic_attributes: o.generic_attributes,
+      tag_attributes: o.tag_attributes,
+      url_schemes: o.url_schemes,
+      iframe_hosts: o.iframe_hosts,
+    })
+    .unwrap_or_default();
+  let (html, stripped) = sanitizer::sanitize(&html, &options);
+  Ok(SanitizedHtml {
+    html,
+    stripped: stripped.into_iter().map(StrippedHtml::from).collect(),
+  })
+}
+
 #[napi]
 pub fn log_info(message: String) -> napi::Result<()> {
   init_logger();
@@ -99,7 +614,7 @@ pub fn log_debug(message: String) -> napi::Result<()> {
 
 #[napi]
 pub fn set_log_level(level: String) -> napi::Result<()> {
-  logger::set_log_level(&level).map_err(|e| napi::Error::from_reason(e))
+  logger::set_log_level(&level).map_err(napi::Error::from_reason)
 }
 
 #[napi]
@@ -115,7 +630,7 @@ pub async fn http_get(
 ) -> napi::Result<String> {
   http_client::http_get(&base_url, &path, token.as_deref())
     .await
-    .map_err(|e| napi::Error::from_reason(e))
+    .map_err(napi::Error::from_reason)
 }
 
 #[napi]
@@ -127,7 +642,7 @@ pub async fn http_post(
 ) -> napi::Result<String> {
   http_client::http_post(&base_url, &path, &data, token.as_deref())
     .await
-    .map_err(|e| napi::Error::from_reason(e))
+    .map_err(napi::Error::from_reason)
 }
 
 #[napi]
@@ -138,7 +653,7 @@ pub async fn http_delete(
 ) -> napi::Result<String> {
   http_client::http_delete(&base_url, &path, token.as_deref())
     .await
-    .map_err(|e| napi::Error::from_reason(e))
+    .map_err(napi::Error::from_reason)
 }
 
 #[napi(object)]
@@ -146,6 +661,29 @@ pub struct HighlightOptions {
   pub lang: Option<String>,
   pub caption: Option<String>,
   pub first_line: Option<i32>,
+  pub diagram_languages: Option<Vec<String>>,
+  pub class_prefix: Option<String>,
+  /// Lines rendered with the `marked` class, e.g. `1,4-6`. Numbers include `firstLine`
+  pub mark_lines: Option<String>,
+  /// `table` (default) or `pre`: `<pre><code>` with a `<span class="line" data-line="N">` per line
+  pub layout: Option<String>,
+  /// Line numbers. Default: true
+  pub gutter: Option<bool>,
+  /// Empty `<button class="copy-button">` for a client side script. Default: false
+  pub copy_button: Option<bool>,
+  /// `<span class="highlight-lang">` with the language. Default: false
+  pub language_label: Option<bool>,
+}
+
+fn highlight_layout(layout: Option<String>) -> napi::Result<highlight::HighlightLayout> {
+  match layout.as_deref() {
+    None | Some("table") => Ok(highlight::HighlightLayout::Table),
+    Some("pre") => Ok(highlight::HighlightLayout::Pre),
+    Some(other) => Err(napi::Error::from_reason(format!(
+      "layout must be `table` or `pre`, but got `{}`",
+      other
+    ))),
+  }
 }
 
 #[napi]
@@ -154,10 +692,56 @@ pub fn highlight_code(code: String, options: HighlightOptions) -> napi::Result<S
     lang: options.lang,
     caption: options.caption,
     first_line: options.first_line.unwrap_or(1),
+    diagram_languages: options.diagram_languages,
+    class_prefix: options.class_prefix,
+    mark_lines: options.mark_lines,
+    layout: highlight_layout(options.layout)?,
+    gutter: options.gutter.unwrap_or(true),
+    copy_button: options.copy_button.unwrap_or_default(),
+    language_label: options.language_label.unwrap_or_default(),
   };
   Ok(highlight::highlight(&code, opts))
 }
 
+#[napi(object)]
+pub struct HighlighterOptions {
+  /// Directory searched recursively for `.sublime-syntax` files
+  pub syntax_dir: Option<String>,
+  /// Syntax set dumped with syntect, used instead of the defaults
+  pub syntax_dump: Option<String>,
+  /// Language aliases, e.g. `{ tf: "hcl" }`
+  pub aliases: Option<HashMap<String, String>>,
+}
+
+#[napi]
+pub fn configure_highlighter(options: HighlighterOptions) -> napi::Result<()> {
+  let config = highlight::SyntaxConfig {
+    syntax_dir: options.syntax_dir.map(Into::into),
+    syntax_dump: options.syntax_dump.map(Into::into),
+    aliases: options.aliases.unwrap_or_default(),
+  };
+  highlight::configure(&config).map_err(napi::Error::from_reason)
+}
+
+#[napi(object)]
+pub struct ThemeCssOptions {
+  /// Name of a syntect default theme (e.g. `InspiredGitHub`) or path of a `.tmTheme` file
+  pub theme: String,
+  /// Theme for `prefers-color-scheme: dark`. `theme` is then used for light
+  pub dark_theme: Option<String>,
+  pub class_prefix: Option<String>,
+}
+
+#[napi]
+pub fn highlight_theme_css(options: ThemeCssOptions) -> napi::Result<String> {
+  let opts = highlight::ThemeCssOptions {
+    theme: options.theme,
+    dark_theme: options.dark_theme,
+    class_prefix: options.class_prefix,
+  };
+  highlight::theme_css(&opts).map_err(napi::Error::from_reason)
+}
+
 #[napi]
 pub fn is_valid_image(source: String) -> napi::Result<bool> {
   // TODO: move somewhere
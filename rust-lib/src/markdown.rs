@@ -1,82 +1,293 @@
-use std::sync::LazyLock;
+mod abbr;
+mod admonition;
+mod anchor;
+mod ast;
+mod blockquote;
+mod cjk;
+mod deflist;
+mod embed;
+mod emoji;
+mod fence;
+mod footnote;
+mod image;
+mod links;
+mod lint;
+mod math;
+mod meta;
+mod quotes;
+mod ruby;
+mod task_list;
+mod toc;
 
-use markdown_it::MarkdownIt;
-use markdown_it_footnote;
-use markdown_it_lazyload;
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock, Mutex};
 
-static PARSER: LazyLock<MarkdownIt> = LazyLock::new(|| {
+use markdown_it::{MarkdownIt, Node};
+
+use crate::highlight::HighlightLayout;
+use fence::HighlightSettings;
+
+pub use ast::AstNode;
+pub use footnote::FootnoteOptions;
+pub use image::ImageOptions;
+pub use links::{BrokenLinkKind, LinkCheckOptions, LinkDiagnostic};
+pub use lint::{LintDiagnostic, LintFix, Severity};
+pub use math::MathError;
+pub use meta::Meta;
+pub use quotes::DEFAULT_QUOTES;
+pub use toc::TocItem;
+
+/// Plugins and settings of the markdown parser.
+/// The default enables everything except the opt-in syntax extensions (task lists, definition lists,
+/// abbreviations and emoji).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MarkdownOptions {
+  pub html: bool,
+  pub typographer: bool,
+  pub smartquotes: bool,
+  /// `[double open, double close, single open, single close]`
+  pub quotes: [char; 4],
+  pub tables: bool,
+  pub strikethrough: bool,
+  pub footnotes: bool,
+  pub lazyload: bool,
+  pub heading_anchors: bool,
+  pub blockquote_paragraphs: bool,
+  pub admonitions: bool,
+  pub math: bool,
+  /// Bare urls of known providers (YouTube, X/Twitter, GitHub gists and SpeakerDeck) alone in a paragraph
+  pub embeds: bool,
+  pub ruby: bool,
+  /// CJK aware softbreaks and quotes
+  pub cjk: bool,
+  pub task_lists: bool,
+  pub definition_lists: bool,
+  pub abbreviations: bool,
+  pub emoji: bool,
+  pub highlight_class_prefix: Option<String>,
+  pub highlight_layout: HighlightLayout,
+  pub highlight_gutter: bool,
+  pub highlight_copy_button: bool,
+  pub highlight_language_label: bool,
+  /// Fence languages rendered as diagram containers. `None` means `DEFAULT_DIAGRAM_LANGUAGES`.
+  pub diagram_languages: Option<Vec<String>>,
+  /// Applied to each document after parsing, see `ImageOptions`.
+  pub images: ImageOptions,
+  /// Applied to each document after parsing, see `FootnoteOptions`.
+  pub footnote_options: FootnoteOptions,
+}
+
+impl Default for MarkdownOptions {
+  fn default() -> Self {
+    Self {
+      html: true,
+      typographer: true,
+      smartquotes: true,
+      quotes: DEFAULT_QUOTES,
+      tables: true,
+      strikethrough: true,
+      footnotes: true,
+      lazyload: true,
+      heading_anchors: true,
+      blockquote_paragraphs: true,
+      admonitions: true,
+      math: false,
+      embeds: false,
+      ruby: false,
+      cjk: false,
+      task_lists: false,
+      definition_lists: false,
+      abbreviations: false,
+      emoji: false,
+      highlight_class_prefix: None,
+      highlight_layout: HighlightLayout::Table,
+      highlight_gutter: true,
+      highlight_copy_button: false,
+      highlight_language_label: false,
+      diagram_languages: None,
+      images: ImageOptions::default(),
+      footnote_options: FootnoteOptions::default(),
+    }
+  }
+}
+
+/// Parsers are built once per distinct option set.
+static PARSERS: LazyLock<Mutex<HashMap<MarkdownOptions, Arc<MarkdownIt>>>> =
+  LazyLock::new(|| Mutex::new(HashMap::new()));
+
+fn parser(options: &MarkdownOptions) -> Arc<MarkdownIt> {
+  // image and footnote options differ per document but don't change the parser
+  let key = MarkdownOptions {
+    images: ImageOptions::default(),
+    footnote_options: FootnoteOptions::default(),
+    ..options.clone()
+  };
+  let mut parsers = PARSERS.lock().unwrap();
+  parsers
+    .entry(key)
+    .or_insert_with(|| Arc::new(build_parser(options)))
+    .clone()
+}
+
+/// Parses markdown and applies the per-document post-processing.
+fn parse(input: &str, options: &MarkdownOptions) -> Node {
+  let mut ast = parser(options).parse(input);
+  image::process(&mut ast, &options.images);
+  footnote::process(&mut ast, &options.footnote_options);
+  ast
+}
+
+fn build_parser(options: &MarkdownOptions) -> MarkdownIt {
   let mut parser = markdown_it::MarkdownIt::new();
 
   markdown_it::plugins::cmark::add(&mut parser);
 
-  markdown_it::plugins::extra::smartquotes::add(&mut parser);
-  markdown_it::plugins::extra::tables::add(&mut parser);
-  markdown_it::plugins::extra::strikethrough::add(&mut parser);
-  markdown_it::plugins::extra::syntect::add(&mut parser);
-  markdown_it::plugins::extra::typographer::add(&mut parser);
-  markdown_it::plugins::html::add(&mut parser);
-
-  markdown_it_footnote::definitions::add(&mut parser);
-  markdown_it_footnote::references::add(&mut parser);
-  markdown_it_footnote::inline::add(&mut parser);
-  markdown_it_footnote::collect::add(&mut parser);
-  markdown_it_footnote::back_refs::add(&mut parser);
+  if options.smartquotes {
+    quotes::add(&mut parser, options.quotes);
+  }
+  if options.tables {
+    markdown_it::plugins::extra::tables::add(&mut parser);
+  }
+  if options.strikethrough {
+    markdown_it::plugins::extra::strikethrough::add(&mut parser);
+  }
+  if options.typographer {
+    markdown_it::plugins::extra::typographer::add(&mut parser);
+  }
+  if options.html {
+    markdown_it::plugins::html::add(&mut parser);
+  }
 
-  markdown_it_lazyload::add(&mut parser);
+  if options.footnotes {
+    markdown_it_footnote::definitions::add(&mut parser);
+    markdown_it_footnote::references::add(&mut parser);
+    markdown_it_footnote::inline::add(&mut parser);
+    markdown_it_footnote::collect::add(&mut parser);
+    markdown_it_footnote::back_refs::add(&mut parser);
+  }
 
-  parser
-});
-
-/// Preprocesses blockquotes to render consecutive blockquote lines as separate paragraphs.
-///
-/// This function inserts an empty blockquote line (">") between consecutive blockquote lines,
-/// causing the markdown parser to render each line as a separate paragraph within the same blockquote.
-///
-/// # Examples
-///
-/// ```
-/// // Input:
-/// // > Line 1
-/// // > Line 2
-///
-/// // After preprocessing:
-/// // > Line 1
-/// // >
-/// // > Line 2
-///
-/// // Rendered output:
-/// // <blockquote>
-/// // <p>Line 1</p>
-/// // <p>Line 2</p>
-/// // </blockquote>
-/// ```
-fn preprocess_blockquotes(input: &str) -> String {
-  let lines: Vec<&str> = input.lines().collect();
-  let mut result = Vec::new();
-  let mut prev_was_blockquote = false;
-
-  for line in lines {
-    let trimmed = line.trim_start();
-    let is_blockquote = trimmed.starts_with('>');
-    let is_empty_blockquote = trimmed == ">";
-
-    if is_blockquote && prev_was_blockquote && !is_empty_blockquote {
-      result.push(">");
-    }
+  if options.lazyload {
+    markdown_it_lazyload::add(&mut parser);
+  }
 
-    result.push(line);
-    prev_was_blockquote = is_blockquote;
+  if options.heading_anchors {
+    anchor::add(&mut parser);
+  }
+  if options.blockquote_paragraphs {
+    blockquote::add(&mut parser);
+  }
+  if options.ruby {
+    ruby::add(&mut parser);
+  }
+  if options.cjk {
+    // custom quotes (e.g. 「」『』) are meant to be used with CJK text
+    cjk::add(&mut parser, options.quotes == DEFAULT_QUOTES);
+  }
+  if options.task_lists {
+    task_list::add(&mut parser);
+  }
+  if options.definition_lists {
+    deflist::add(&mut parser);
+  }
+  if options.abbreviations {
+    abbr::add(&mut parser);
   }
+  if options.emoji {
+    emoji::add(&mut parser);
+  }
+  if options.admonitions {
+    admonition::add(&mut parser);
+  }
+  if options.math {
+    math::add(&mut parser);
+  }
+  if options.embeds {
+    embed::add(&mut parser);
+  }
+  fence::add(&mut parser);
+  fence::set_settings(
+    &mut parser,
+    HighlightSettings {
+      class_prefix: options.highlight_class_prefix.clone(),
+      layout: options.highlight_layout,
+      gutter: options.highlight_gutter,
+      copy_button: options.highlight_copy_button,
+      language_label: options.highlight_language_label,
+      diagram_languages: options.diagram_languages.clone(),
+    },
+  );
 
-  result.join("\n")
+  parser
 }
 
-pub fn render(input: &str) -> String {
-  let preprocessed = preprocess_blockquotes(input);
-  let ast = PARSER.parse(&preprocessed);
+pub fn render(input: &str, options: &MarkdownOptions) -> String {
+  let ast = parse(input, options);
   ast.render()
 }
 
+/// Renders markdown and returns the HTML together with the table of contents built from its headings.
+pub fn render_with_toc(input: &str, options: &MarkdownOptions) -> (String, Vec<TocItem>) {
+  let ast = parse(input, options);
+  (ast.render(), toc::collect(&ast))
+}
+
+/// Renders markdown and returns the HTML together with its plain text and text statistics.
+pub fn render_with_meta(input: &str, options: &MarkdownOptions) -> (String, Meta) {
+  let ast = parse(input, options);
+  (ast.render(), meta::collect(&ast))
+}
+
+/// Renders markdown and returns the HTML together with math expressions that failed to parse.
+pub fn render_with_math_errors(input: &str, options: &MarkdownOptions) -> (String, Vec<MathError>) {
+  let ast = parse(input, options);
+  (ast.render(), math::collect_errors(&ast))
+}
+
+/// Renders markdown for the published payload: the HTML, math expressions that failed to parse and the
+/// table of contents, from a single parse.
+pub fn render_for_payload(
+  input: &str,
+  options: &MarkdownOptions,
+) -> (String, Vec<MathError>, Vec<TocItem>) {
+  let ast = parse(input, options);
+  (ast.render(), math::collect_errors(&ast), toc::collect(&ast))
+}
+
+/// Returns broken fragments, content links and footnote references of the markdown.
+pub fn check_links(
+  input: &str,
+  options: &MarkdownOptions,
+  links: &LinkCheckOptions,
+) -> Vec<LinkDiagnostic> {
+  let ast = parse(input, options);
+  links::check(&ast, links, options.footnotes)
+}
+
+/// Lints markdown, which may start with a `---` front matter.
+pub fn lint(input: &str, options: &MarkdownOptions) -> Vec<LintDiagnostic> {
+  let offset = lint::front_matter_len(input);
+  let source = &input[offset..];
+  let ast = parse(source, options);
+  let document = lint::Document {
+    source,
+    ast: &ast,
+    footnotes: options.footnotes,
+    diagram_languages: options.diagram_languages.as_deref(),
+  };
+  lint::check(input, offset, &document)
+}
+
+/// Applies the fixes of the lint diagnostics and returns the fixed markdown.
+pub fn fix(input: &str, options: &MarkdownOptions) -> String {
+  lint::apply_fixes(input, &lint(input, options))
+}
+
+/// Parses markdown into the same AST that is rendered, e.g. for linters and transforms.
+pub fn ast(input: &str, options: &MarkdownOptions) -> AstNode {
+  let ast = parse(input, options);
+  ast::collect(&ast)
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
@@ -84,7 +295,7 @@ mod tests {
   #[test]
   fn test_render() {
     let input = "![Rust](https://example.com/example.png)";
-    let output = render(input);
+    let output = render(input, &MarkdownOptions::default());
 
     assert_eq!(
       output,
@@ -95,7 +306,7 @@ mod tests {
   #[test]
   fn test_consecutive_blockquote_lines() {
     let input = "> Line 1\n> Line 2\n> Line 3";
-    let output = render(input);
+    let output = render(input, &MarkdownOptions::default());
 
     assert_eq!(
       output,
@@ -106,7 +317,7 @@ mod tests {
   #[test]
   fn test_blockquote_with_existing_blank_lines() {
     let input = "> Line 1\n>\n> Line 2";
-    let output = render(input);
+    let output = render(input, &MarkdownOptions::default());
 
     assert_eq!(
       output,
@@ -117,8 +328,593 @@ mod tests {
   #[test]
   fn test_single_blockquote_line() {
     let input = "> Single line";
-    let output = render(input);
+    let output = render(input, &MarkdownOptions::default());
 
     assert_eq!(output, "<blockquote>\n<p>Single line</p>\n</blockquote>\n");
   }
+
+  #[test]
+  fn test_blockquote_lazy_continuation() {
+    let input = "> Line 1\ncontinued\n> Line 2";
+    let output = render(input, &MarkdownOptions::default());
+
+    assert_eq!(
+      output,
+      "<blockquote>\n<p>Line 1\ncontinued</p>\n<p>Line 2</p>\n</blockquote>\n"
+    );
+  }
+
+  #[test]
+  fn test_blockquote_hardbreak_kept() {
+    let input = "> Line 1  \n> Line 2";
+    let output = render(input, &MarkdownOptions::default());
+
+    assert_eq!(
+      output,
+      "<blockquote>\n<p>Line 1<br>\nLine 2</p>\n</blockquote>\n"
+    );
+  }
+
+  #[test]
+  fn test_blockquote_with_code_fence() {
+    let input = "> Before\n> ```\n> line a\n> line b\n> ```\n> After";
+    let output = render(input, &MarkdownOptions::default());
+
+    assert!(output.starts_with("<blockquote>\n<p>Before</p>\n"));
+    assert!(output.contains("<span class=\"line\">line a</span>"));
+    assert!(output.contains("<span class=\"line\">line b</span>"));
+    assert!(!output.contains("&gt;"));
+    assert!(output.ends_with("<p>After</p>\n</blockquote>\n"));
+  }
+
+  #[test]
+  fn test_nested_blockquotes() {
+    let input = "> Outer 1\n> Outer 2\n>\n> > Inner 1\n> > Inner 2\n> lazy";
+    let output = render(input, &MarkdownOptions::default());
+
+    assert_eq!(
+      output,
+      "<blockquote>\n<p>Outer 1</p>\n<p>Outer 2</p>\n<blockquote>\n<p>Inner 1</p>\n<p>Inner 2\nlazy</p>\n</blockquote>\n</blockquote>\n"
+    );
+  }
+
+  #[test]
+  fn test_list_inside_blockquote() {
+    let input = "> - item 1\n>   continued\n> - item 2";
+    let output = render(input, &MarkdownOptions::default());
+
+    assert_eq!(
+      output,
+      "<blockquote>\n<ul>\n<li>item 1\ncontinued</li>\n<li>item 2</li>\n</ul>\n</blockquote>\n"
+    );
+  }
+
+  #[test]
+  fn test_admonition() {
+    let input = ":::warning Be *careful*\nThis is **important**.\n\n- item\n:::";
+    let output = render(input, &MarkdownOptions::default());
+
+    assert_eq!(
+      output,
+      "<aside class=\"admonition warning\">\n<p class=\"admonition-title\">Be <em>careful</em></p>\n<p>This is <strong>important</strong>.</p>\n<ul>\n<li>item</li>\n</ul>\n</aside>\n"
+    );
+  }
+
+  #[test]
+  fn test_admonition_default_title() {
+    let input = ":::tip\nUse `cargo fmt`.\n:::\n\nAfter";
+    let output = render(input, &MarkdownOptions::default());
+
+    assert_eq!(
+      output,
+      "<aside class=\"admonition tip\">\n<p class=\"admonition-title\">Tip</p>\n<p>Use <code>cargo fmt</code>.</p>\n</aside>\n<p>After</p>\n"
+    );
+  }
+
+  #[test]
+  fn test_admonition_nested() {
+    let input = "::::note\nOuter\n:::danger\nInner\n:::\n::::";
+    let output = render(input, &MarkdownOptions::default());
+
+    assert_eq!(
+      output,
+      "<aside class=\"admonition note\">\n<p class=\"admonition-title\">Note</p>\n<p>Outer</p>\n<aside class=\"admonition danger\">\n<p class=\"admonition-title\">Danger</p>\n<p>Inner</p>\n</aside>\n</aside>\n"
+    );
+  }
+
+  #[test]
+  fn test_admonition_unknown_kind() {
+    let input = ":::unknown\ntext\n:::";
+    let output = render(input, &MarkdownOptions::default());
+
+    assert_eq!(output, "<p>:::unknown\ntext\n:::</p>\n");
+  }
+
+  #[test]
+  fn test_admonition_escapes_html() {
+    let input = ":::note <b>title</b>\n:::";
+    let output = render(input, &MarkdownOptions::default());
+
+    assert!(output.contains("<p class=\"admonition-title\"><b>title</b></p>"));
+
+    let input = ":::note\n1 < 2\n:::";
+    let output = render(input, &MarkdownOptions::default());
+
+    assert!(output.contains("<p>1 &lt; 2</p>"));
+  }
+
+  #[test]
+  fn test_github_alert() {
+    let input = "> [!NOTE]\n> Line 1\n> Line 2";
+    let output = render(input, &MarkdownOptions::default());
+
+    assert_eq!(
+      output,
+      "<aside class=\"admonition note\">\n<p class=\"admonition-title\">Note</p>\n<p>Line 1</p>\n<p>Line 2</p>\n</aside>\n"
+    );
+  }
+
+  #[test]
+  fn test_github_alert_without_blockquote_paragraphs() {
+    let options = MarkdownOptions {
+      blockquote_paragraphs: false,
+      ..Default::default()
+    };
+
+    let output = render("> [!NOTE]\n> Line 1\n> Line 2", &options);
+    assert_eq!(
+      output,
+      "<aside class=\"admonition note\">\n<p class=\"admonition-title\">Note</p>\n<p>Line 1\nLine 2</p>\n</aside>\n"
+    );
+
+    let output = render("> [!warning] Read *this*\n> Body", &options);
+    assert_eq!(
+      output,
+      "<aside class=\"admonition warning\">\n<p class=\"admonition-title\">Read <em>this</em></p>\n<p>Body</p>\n</aside>\n"
+    );
+  }
+
+  #[test]
+  fn test_github_alert_with_title() {
+    let input = "> [!warning] Read this\n> Body";
+    let output = render(input, &MarkdownOptions::default());
+
+    assert_eq!(
+      output,
+      "<aside class=\"admonition warning\">\n<p class=\"admonition-title\">Read this</p>\n<p>Body</p>\n</aside>\n"
+    );
+  }
+
+  #[test]
+  fn test_github_alert_unknown_kind() {
+    let input = "> [!OTHER]\n> Body";
+    let output = render(input, &MarkdownOptions::default());
+
+    assert_eq!(
+      output,
+      "<blockquote>\n<p>[!OTHER]</p>\n<p>Body</p>\n</blockquote>\n"
+    );
+  }
+
+  #[test]
+  fn test_heading_anchors() {
+    let input = "# Hello World\n\n## 日本語の見出し\n\n## Hello World";
+    let output = render(input, &MarkdownOptions::default());
+
+    assert_eq!(
+      output,
+      "<h1 id=\"hello-world\">Hello World</h1>\n<h2 id=\"日本語の見出し\">日本語の見出し</h2>\n<h2 id=\"hello-world-1\">Hello World</h2>\n"
+    );
+  }
+
+  #[test]
+  fn test_render_with_toc() {
+    let input = "# Title\n\n## Section A\n\n### Detail\n\nSection B\n---------";
+    let (html, toc) = render_with_toc(input, &MarkdownOptions::default());
+
+    assert!(html.contains("<h3 id=\"detail\">Detail</h3>"));
+    assert!(html.contains("<h2 id=\"section-b\">Section B</h2>"));
+    assert_eq!(toc.len(), 1);
+    assert_eq!(toc[0].id, "title");
+    assert_eq!(toc[0].children.len(), 2);
+    assert_eq!(toc[0].children[0].text, "Section A");
+    assert_eq!(toc[0].children[0].children[0].level, 3);
+    assert_eq!(toc[0].children[1].id, "section-b");
+  }
+
+  #[test]
+  fn test_render_with_toc_no_headings() {
+    let (html, toc) = render_with_toc("plain text", &MarkdownOptions::default());
+
+    assert_eq!(html, "<p>plain text</p>\n");
+    assert!(toc.is_empty());
+  }
+
+  #[test]
+  fn test_render_with_meta() {
+    let input = "# Title\n\nFirst *paragraph* with a [link](https://example.com).\n\n```rust\nlet x = 1;\n```\n\n- item one\n- item two\n\n<div>raw html</div>";
+    let (html, meta) = render_with_meta(input, &MarkdownOptions::default());
+
+    assert!(html.contains("<em>paragraph</em>"));
+    assert_eq!(
+      meta.plain_text,
+      "Title\nFirst paragraph with a link.\nitem one\nitem two"
+    );
+    assert_eq!(meta.excerpt, "First paragraph with a link.");
+    assert_eq!(meta.word_count, 10);
+    assert_eq!(meta.character_count, 43);
+    assert_eq!(meta.reading_time_minutes, 1);
+  }
+
+  #[test]
+  fn test_render_with_meta_cjk() {
+    let input = "これは日本語の文章です。\nRust を使います。";
+    let (_, meta) = render_with_meta(input, &MarkdownOptions::default());
+
+    assert_eq!(meta.excerpt, "これは日本語の文章です。 Rust を使います。");
+    // 16 CJK characters and 1 latin word
+    assert_eq!(meta.word_count, 17);
+    assert_eq!(meta.character_count, 22);
+    assert_eq!(meta.reading_time_minutes, 1);
+  }
+
+  #[test]
+  fn test_render_with_meta_empty() {
+    let (_, meta) = render_with_meta("", &MarkdownOptions::default());
+
+    assert_eq!(meta.plain_text, "");
+    assert_eq!(meta.excerpt, "");
+    assert_eq!(meta.word_count, 0);
+    assert_eq!(meta.reading_time_minutes, 0);
+  }
+
+  fn math_options() -> MarkdownOptions {
+    MarkdownOptions {
+      math: true,
+      ..Default::default()
+    }
+  }
+
+  #[test]
+  fn test_math_is_not_typographed() {
+    let (output, errors) = render_with_math_errors("\"quote\" $f'(x)$", &math_options());
+    assert!(output.starts_with("<p>“quote” <math"));
+    assert!(output.contains("<msup><mi>f</mi><mo>′</mo></msup>"));
+    assert!(errors.is_empty());
+  }
+
+  #[test]
+  fn test_render_for_payload() {
+    let (html, errors, toc) = render_for_payload("# Title\n\n$$\n\\frac{a\n$$", &math_options());
+    assert!(html.starts_with("<h1 id=\"title\">Title</h1>"));
+    assert_eq!(errors.len(), 1);
+    assert_eq!(toc.len(), 1);
+    assert_eq!(toc[0].id, "title");
+  }
+
+  #[test]
+  fn test_math_errors() {
+    let (output, errors) = render_with_math_errors("# Title\n\n$$\n\\frac{a\n$$", &math_options());
+    assert!(output.contains("<span class=\"math\">\\frac{a</span>"));
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].line, 3);
+  }
+
+  #[test]
+  fn test_diagram_fence() {
+    let input = "```mermaid\ngraph TD;\n  A-->B;\n```";
+    let output = render(input, &MarkdownOptions::default());
+
+    assert_eq!(
+      output,
+      "<pre class=\"mermaid\">graph TD;\n  A--&gt;B;</pre>\n"
+    );
+  }
+
+  #[test]
+  fn test_diagram_languages_option() {
+    let input = "```ditaa\n+--+\n```\n\n```mermaid\ngraph TD;\n```";
+    let default = render(input, &MarkdownOptions::default());
+    assert!(default.starts_with("<figure class=\"highlight ditaa\">"));
+    assert!(default.ends_with("<pre class=\"mermaid\">graph TD;</pre>\n"));
+
+    let options = MarkdownOptions {
+      diagram_languages: Some(vec!["ditaa".to_string()]),
+      ..Default::default()
+    };
+    let output = render(input, &options);
+    assert!(
+      output.starts_with("<pre class=\"ditaa\">+--+</pre>\n<figure class=\"highlight mermaid\">")
+    );
+  }
+
+  #[test]
+  fn test_code_fence() {
+    let input = "```js Example https://example.com source =3\nconst x = 1;\n```";
+    let output = render(input, &MarkdownOptions::default());
+
+    assert!(output.starts_with("<figure class=\"highlight js\"><figcaption><span>Example</span><a href=\"https://example.com\">source</a></figcaption>"));
+    assert!(
+      output.contains("<td class=\"gutter\"><pre><span class=\"line\">3</span><br></pre></td>")
+    );
+    assert!(!output.contains("<span class=\"line\">4</span>"));
+  }
+
+  #[test]
+  fn test_code_fence_in_blockquote() {
+    let input = "> ```rust\n> let x = 1;\n> ```";
+    let output = render(input, &MarkdownOptions::default());
+
+    assert!(output.starts_with("<blockquote>\n<figure class=\"highlight rust\">"));
+    assert!(!output.contains("&gt; let"));
+  }
+
+  #[test]
+  fn test_options_disable_plugins() {
+    let options = MarkdownOptions {
+      html: false,
+      typographer: false,
+      smartquotes: false,
+      lazyload: false,
+      ..Default::default()
+    };
+    let output = render("<b>\"a\"</b> (c) ![x](x.png)", &options);
+
+    assert_eq!(
+      output,
+      "<p>&lt;b&gt;&quot;a&quot;&lt;/b&gt; (c) <img src=\"x.png\" alt=\"x\"></p>\n"
+    );
+  }
+
+  #[test]
+  fn test_options_quotes() {
+    let options = MarkdownOptions {
+      quotes: ['「', '」', '『', '』'],
+      ..Default::default()
+    };
+    let output = render("\"こんにちは\"", &options);
+
+    assert_eq!(output, "<p>「こんにちは」</p>\n");
+  }
+
+  #[test]
+  fn test_parsers_are_cached_per_options() {
+    let options = math_options();
+    assert!(Arc::ptr_eq(&parser(&options), &parser(&options.clone())));
+    assert!(!Arc::ptr_eq(
+      &parser(&options),
+      &parser(&MarkdownOptions::default())
+    ));
+
+    let per_document = MarkdownOptions {
+      images: ImageOptions {
+        url_prefix: Some("/articles/example/".to_string()),
+        ..Default::default()
+      },
+      footnote_options: FootnoteOptions {
+        id_prefix: Some("articles-example-".to_string()),
+        ..Default::default()
+      },
+      ..options.clone()
+    };
+    assert!(Arc::ptr_eq(&parser(&options), &parser(&per_document)));
+  }
+
+  fn cjk_options() -> MarkdownOptions {
+    MarkdownOptions {
+      cjk: true,
+      ..Default::default()
+    }
+  }
+
+  #[test]
+  fn test_cjk_softbreak() {
+    let input = "日本語の\n文章です。\n次の行\nEnglish line\nと日本語";
+    let output = render(input, &cjk_options());
+
+    assert_eq!(
+      output,
+      "<p>日本語の文章です。次の行\nEnglish line\nと日本語</p>\n"
+    );
+  }
+
+  #[test]
+  fn test_cjk_softbreak_across_inline() {
+    let input = "**強調**\n文章";
+    let output = render(input, &cjk_options());
+
+    assert_eq!(output, "<p><strong>強調</strong>文章</p>\n");
+  }
+
+  #[test]
+  fn test_cjk_softbreak_in_blockquote() {
+    let input = "> 一行目\n> 二行目";
+    let output = render(input, &cjk_options());
+
+    assert_eq!(
+      output,
+      "<blockquote>\n<p>一行目</p>\n<p>二行目</p>\n</blockquote>\n"
+    );
+  }
+
+  #[test]
+  fn test_cjk_quotes_left_alone() {
+    let input = "日本語'テスト'です。「\"Hello\"」と\"English\" 'words' it's";
+    let output = render(input, &cjk_options());
+
+    assert_eq!(
+      output,
+      "<p>日本語'テスト'です。「&quot;Hello&quot;」と&quot;English&quot; ‘words’ it’s</p>\n"
+    );
+  }
+
+  #[test]
+  fn test_typographer_leaves_fullwidth_punctuation() {
+    let input = "（c）すごい！！！！ええ、、本当？？？？ (c)...";
+    let output = render(input, &MarkdownOptions::default());
+
+    assert_eq!(
+      output,
+      "<p>（c）すごい！！！！ええ、、本当？？？？ ©…</p>\n"
+    );
+  }
+
+  #[test]
+  fn test_cjk_disabled() {
+    let output = render("日本語の\n文章", &MarkdownOptions::default());
+
+    assert_eq!(output, "<p>日本語の\n文章</p>\n");
+  }
+
+  #[test]
+  fn test_ruby() {
+    let input = "{漢字|かんじ}と[東京]^(とうきょう)";
+    let options = MarkdownOptions {
+      ruby: true,
+      ..Default::default()
+    };
+    let (output, meta) = render_with_meta(input, &options);
+
+    assert_eq!(
+      output,
+      "<p><ruby><rb>漢字</rb><rp>(</rp><rt>かんじ</rt><rp>)</rp></ruby>と<ruby><rb>東京</rb><rp>(</rp><rt>とうきょう</rt><rp>)</rp></ruby></p>\n"
+    );
+    assert_eq!(meta.plain_text, "漢字と東京");
+  }
+
+  #[test]
+  fn test_opt_in_syntax_extensions() {
+    let input = "*[GFM]: GitHub Flavored Markdown\n\n- [x] GFM :tada:\n\nTerm\n: Definition";
+
+    let output = render(input, &MarkdownOptions::default());
+    assert_eq!(
+      output,
+      "<p>*[GFM]: GitHub Flavored Markdown</p>\n<ul>\n<li>[x] GFM :tada:</li>\n</ul>\n<p>Term\n: Definition</p>\n"
+    );
+
+    let options = MarkdownOptions {
+      task_lists: true,
+      definition_lists: true,
+      abbreviations: true,
+      emoji: true,
+      ..Default::default()
+    };
+    let output = render(input, &options);
+    assert_eq!(
+      output,
+      "<ul class=\"contains-task-list\">\n<li class=\"task-list-item\"><input class=\"task-list-item-checkbox\" checked=\"\" disabled=\"\" type=\"checkbox\"> <abbr title=\"GitHub Flavored Markdown\">GFM</abbr> 🎉</li>\n</ul>\n\
+       <dl>\n<dt>Term</dt>\n<dd>Definition</dd>\n</dl>\n"
+    );
+  }
+
+  #[test]
+  fn test_image_with_title_is_a_figure() {
+    let input = "![Rust](rust.png \"The Rust logo\")";
+    let options = MarkdownOptions {
+      images: ImageOptions {
+        url_prefix: Some("/articles/2024/02/example/".to_string()),
+        ..Default::default()
+      },
+      ..Default::default()
+    };
+    let output = render(input, &options);
+
+    assert_eq!(
+      output,
+      "<figure><img src=\"/articles/2024/02/example/rust.png\" alt=\"Rust\" loading=\"lazy\"><figcaption>The Rust logo</figcaption></figure>\n"
+    );
+  }
+
+  #[test]
+  fn test_check_links() {
+    let input = "# Intro\n\n[a](#intro) [b](#outro) [c](/articles/other/)[^1]";
+    let links = LinkCheckOptions {
+      content_path: "/articles/example/".to_string(),
+      known_paths: vec!["/articles/other/".to_string()],
+    };
+    let diagnostics = check_links(input, &MarkdownOptions::default(), &links);
+
+    let targets: Vec<(BrokenLinkKind, &str)> = diagnostics
+      .iter()
+      .map(|d| (d.kind, d.target.as_str()))
+      .collect();
+    assert_eq!(
+      targets,
+      vec![
+        (BrokenLinkKind::Fragment, "#outro"),
+        (BrokenLinkKind::Footnote, "[^1]")
+      ]
+    );
+  }
+
+  #[test]
+  fn test_ast_matches_rendered_document() {
+    let input = "## Title\n\n```rust\nfn main() {}\n```\n\n![A](a.png \"Caption\")";
+    let ast = ast(input, &MarkdownOptions::default());
+
+    let types: Vec<&str> = ast.children.iter().map(|n| n.node_type.as_str()).collect();
+    assert_eq!(types, vec!["ATXHeading", "HighlightedCode", "Figure"]);
+
+    let heading = &ast.children[0];
+    assert_eq!(heading.attrs["id"], "title");
+    assert_eq!(heading.lines, Some([1, 1]));
+
+    let code = &ast.children[1];
+    assert_eq!(code.attrs["lang"], "rust");
+    assert_eq!(code.attrs["content"], "fn main() {}");
+    assert_eq!(code.lines, Some([3, 5]));
+
+    let figure = &ast.children[2];
+    assert_eq!(figure.attrs["caption"], "Caption");
+    assert_eq!(figure.children[0].node_type, "ProcessedImage");
+  }
+
+  #[test]
+  fn test_embeds() {
+    let input = "https://youtu.be/dQw4w9WgXcQ";
+    let options = MarkdownOptions {
+      embeds: true,
+      ..Default::default()
+    };
+    assert!(render(input, &options).starts_with("<div class=\"embed embed-youtube\""));
+
+    assert_eq!(
+      render(input, &MarkdownOptions::default()),
+      "<p>https://youtu.be/dQw4w9WgXcQ</p>\n"
+    );
+  }
+
+  #[test]
+  fn test_lint_and_fix() {
+    let input = "---\ntitle: example\n---\n# A\n\n### B \n\nhttps://youtu.be/dQw4w9WgXcQ\n";
+    let options = MarkdownOptions {
+      embeds: true,
+      ..Default::default()
+    };
+    let diagnostics = lint(input, &options);
+    let rules: Vec<(&str, usize)> = diagnostics.iter().map(|d| (d.rule, d.line)).collect();
+    assert_eq!(
+      rules,
+      vec![("heading-increment", 6), ("no-trailing-spaces", 6)]
+    );
+
+    assert_eq!(
+      fix(input, &options),
+      "---\ntitle: example\n---\n# A\n\n## B\n\nhttps://youtu.be/dQw4w9WgXcQ\n"
+    );
+  }
+
+  #[test]
+  fn test_footnote_options() {
+    let options = MarkdownOptions {
+      footnote_options: FootnoteOptions {
+        id_prefix: Some("post-".to_string()),
+        ..Default::default()
+      },
+      ..Default::default()
+    };
+    let output = render("a[^1]\n\n[^1]: b", &options);
+    assert!(output.contains("<a href=\"#post-fn1\" id=\"post-fnref1\">"));
+    assert!(output.contains("<li id=\"post-fn1\" class=\"footnote-item\">"));
+  }
 }
@@ -0,0 +1,262 @@
+use markdown_it::parser::block::{BlockRule, BlockState};
+use markdown_it::parser::core::CoreRule;
+use markdown_it::parser::inline::Text;
+use markdown_it::parser::inline::builtin::InlineParserRule;
+use markdown_it::plugins::cmark::block::reference::ReferenceScanner;
+use markdown_it::plugins::cmark::inline::autolink::Autolink;
+use markdown_it::plugins::cmark::inline::backticks::CodeInline;
+use markdown_it::plugins::cmark::inline::link::Link;
+use markdown_it::plugins::extra::smartquotes::SmartQuotesRule;
+use markdown_it::plugins::extra::typographer::TypographerRule;
+use markdown_it::{MarkdownIt, Node, NodeValue, Renderer};
+
+use super::quotes::CustomSmartQuotesRule;
+
+#[derive(Debug)]
+pub struct Abbreviation {
+  pub title: String,
+}
+
+impl NodeValue for Abbreviation {
+  fn render(&self, node: &Node, fmt: &mut dyn Renderer) {
+    let mut attrs = node.attrs.clone();
+    attrs.push(("title", self.title.clone()));
+    fmt.open("abbr", &attrs);
+    fmt.contents(&node.children);
+    fmt.close("abbr");
+  }
+}
+
+/// `*[label]: title`, rendered as nothing.
+#[derive(Debug)]
+pub struct AbbreviationDefinition {
+  pub label: String,
+  pub title: String,
+}
+
+impl NodeValue for AbbreviationDefinition {
+  fn render(&self, _: &Node, _: &mut dyn Renderer) {}
+}
+
+/// Adds abbreviations with the same syntax and output as markdown-it-abbr.
+///
+/// # Examples
+///
+/// ```
+/// // Input:
+/// // *[HTML]: Hyper Text Markup Language
+/// // HTML is great.
+///
+/// // Rendered output:
+/// // <p><abbr title="Hyper Text Markup Language">HTML</abbr> is great.</p>
+/// ```
+pub fn add(md: &mut MarkdownIt) {
+  md.block
+    .add_rule::<AbbreviationDefinitionScanner>()
+    .before::<ReferenceScanner>();
+  md.add_rule::<AbbreviationRule>()
+    .after::<InlineParserRule>()
+    .before::<SmartQuotesRule<'‘', '’', '“', '”'>>()
+    .before::<CustomSmartQuotesRule>()
+    .before::<TypographerRule>();
+}
+
+/// Whether `c` can surround an abbreviation or an emoji shortcut, i.e. it is not part of a word.
+pub(super) fn is_word_boundary(c: Option<char>) -> bool {
+  c.is_none_or(|c| !c.is_alphanumeric())
+}
+
+struct AbbreviationDefinitionScanner;
+
+impl BlockRule for AbbreviationDefinitionScanner {
+  fn check(_: &mut BlockState) -> Option<()> {
+    None // can't interrupt anything
+  }
+
+  fn run(state: &mut BlockState) -> Option<(Node, usize)> {
+    if state.line_indent(state.line) >= state.md.max_indent {
+      return None;
+    }
+
+    let line = state.get_line(state.line);
+    let rest = line.strip_prefix("*[")?;
+
+    let mut label = String::new();
+    let mut chars = rest.char_indices();
+    let label_end = loop {
+      match chars.next()? {
+        (_, '\\') => label.push(chars.next()?.1),
+        (i, ']') => break i,
+        (_, '[') => return None,
+        (_, c) => label.push(c),
+      }
+    };
+    let title = rest[label_end + 1..].strip_prefix(':')?.trim().to_string();
+    if label.is_empty() || title.is_empty() {
+      return None;
+    }
+
+    let node = Node::new(AbbreviationDefinition { label, title });
+    Some((node, 1))
+  }
+}
+
+struct AbbreviationRule;
+
+impl CoreRule for AbbreviationRule {
+  fn run(root: &mut Node, _: &MarkdownIt) {
+    // definitions are read from the tree, as plugins may not restore the root extensions
+    let mut abbreviations: Vec<(String, String)> = vec![];
+    root.walk(|node, _| {
+      let Some(definition) = node.cast::<AbbreviationDefinition>() else {
+        return;
+      };
+      // the first definition of a label wins
+      if !abbreviations
+        .iter()
+        .any(|(label, _)| *label == definition.label)
+      {
+        abbreviations.push((definition.label.clone(), definition.title.clone()));
+      }
+    });
+    if abbreviations.is_empty() {
+      return;
+    }
+    // the longest label wins, as with the alternation in markdown-it-abbr
+    abbreviations.sort_by_key(|(label, _)| std::cmp::Reverse(label.len()));
+
+    replace_in_children(root, &abbreviations);
+  }
+}
+
+fn replace_in_children(node: &mut Node, abbreviations: &[(String, String)]) {
+  let mut idx = 0;
+  while idx < node.children.len() {
+    let child = &mut node.children[idx];
+    if child.is::<Link>()
+      || child.is::<Autolink>()
+      || child.is::<CodeInline>()
+      || child.is::<Abbreviation>()
+    {
+      idx += 1;
+      continue;
+    }
+    let Some(text) = child.cast::<Text>() else {
+      replace_in_children(child, abbreviations);
+      idx += 1;
+      continue;
+    };
+
+    let nodes = split_text(&text.content, abbreviations);
+    let len = nodes.len();
+    if len > 1 {
+      node.children.splice(idx..idx + 1, nodes);
+    }
+    idx += len.max(1);
+  }
+}
+
+/// Splits `content` into text and abbreviation nodes, or returns a single node when nothing matches.
+fn split_text(content: &str, abbreviations: &[(String, String)]) -> Vec<Node> {
+  let mut nodes = vec![];
+  let mut last = 0;
+  let mut prev = None;
+
+  for (pos, c) in content.char_indices() {
+    if pos < last || !is_word_boundary(prev) {
+      prev = Some(c);
+      continue;
+    }
+    prev = Some(c);
+
+    let rest = &content[pos..];
+    let found = abbreviations.iter().find(|(label, _)| {
+      rest.starts_with(label.as_str()) && is_word_boundary(rest[label.len()..].chars().next())
+    });
+    let Some((label, title)) = found else {
+      continue;
+    };
+
+    if pos > last {
+      nodes.push(Node::new(Text {
+        content: content[last..pos].to_string(),
+      }));
+    }
+    let mut abbr = Node::new(Abbreviation {
+      title: title.clone(),
+    });
+    abbr.children.push(Node::new(Text {
+      content: label.clone(),
+    }));
+    nodes.push(abbr);
+    last = pos + label.len();
+  }
+
+  if nodes.is_empty() {
+    return vec![Node::new(Text {
+      content: content.to_string(),
+    })];
+  }
+  if last < content.len() {
+    nodes.push(Node::new(Text {
+      content: content[last..].to_string(),
+    }));
+  }
+  nodes
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn parser() -> MarkdownIt {
+    let mut md = MarkdownIt::new();
+    markdown_it::plugins::cmark::add(&mut md);
+    add(&mut md);
+    md
+  }
+
+  #[test]
+  fn test_abbreviation() {
+    let output = parser()
+      .parse("*[HTML]: Hyper Text Markup Language\n*[W3C]:  World Wide Web Consortium\n\nThe HTML specification\nis maintained by the W3C.")
+      .render();
+    assert_eq!(
+      output,
+      "<p>The <abbr title=\"Hyper Text Markup Language\">HTML</abbr> specification\n\
+       is maintained by the <abbr title=\"World Wide Web Consortium\">W3C</abbr>.</p>\n"
+    );
+  }
+
+  #[test]
+  fn test_abbreviation_word_boundary() {
+    let output = parser()
+      .parse("*[HTML]: Hyper Text\n*[HTML5]: Hyper Text 5\n\nHTML5 XHTML HTMLS (HTML)")
+      .render();
+    assert_eq!(
+      output,
+      "<p><abbr title=\"Hyper Text 5\">HTML5</abbr> XHTML HTMLS (<abbr title=\"Hyper Text\">HTML</abbr>)</p>\n"
+    );
+  }
+
+  #[test]
+  fn test_abbreviation_is_escaped_and_skips_links() {
+    let output = parser()
+      .parse("*[A&B]: \"quoted\"\n\nA&B [A&B](/a)")
+      .render();
+    assert_eq!(
+      output,
+      "<p><abbr title=\"&quot;quoted&quot;\">A&amp;B</abbr> <a href=\"/a\">A&amp;B</a></p>\n"
+    );
+  }
+
+  #[test]
+  fn test_not_abbreviation() {
+    let md = parser();
+    assert_eq!(
+      md.parse("*[HTML]:\n\nHTML").render(),
+      "<p>*[HTML]:</p>\n<p>HTML</p>\n"
+    );
+    assert_eq!(md.parse("*[]: empty").render(), "<p>*[]: empty</p>\n");
+  }
+}
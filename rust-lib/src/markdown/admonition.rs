@@ -0,0 +1,250 @@
+use markdown_it::parser::block::{BlockRule, BlockState};
+use markdown_it::parser::core::CoreRule;
+use markdown_it::parser::inline::builtin::InlineParserRule;
+use markdown_it::parser::inline::{InlineRoot, Text};
+use markdown_it::plugins::cmark::block::blockquote::Blockquote;
+use markdown_it::plugins::cmark::block::paragraph::Paragraph;
+use markdown_it::plugins::cmark::inline::newline::{Hardbreak, Softbreak};
+use markdown_it::{MarkdownIt, Node, NodeValue, Renderer};
+
+use super::blockquote::BlockquoteParagraphRule;
+
+const KINDS: &[&str] = &[
+  "note",
+  "tip",
+  "info",
+  "important",
+  "warning",
+  "caution",
+  "danger",
+];
+
+/// GitHub alert types, see: https://docs.github.com/en/get-started/writing-on-github/getting-started-with-writing-and-formatting-on-github/basic-writing-and-formatting-syntax#alerts
+const ALERT_KINDS: &[&str] = &["note", "tip", "important", "warning", "caution"];
+
+#[derive(Debug)]
+pub struct Admonition {
+  pub kind: String,
+}
+
+impl NodeValue for Admonition {
+  fn render(&self, node: &Node, fmt: &mut dyn Renderer) {
+    let mut attrs = node.attrs.clone();
+    attrs.push(("class", format!("admonition {}", self.kind)));
+
+    fmt.cr();
+    fmt.open("aside", &attrs);
+    fmt.cr();
+    fmt.contents(&node.children);
+    fmt.cr();
+    fmt.close("aside");
+    fmt.cr();
+  }
+}
+
+#[derive(Debug)]
+pub struct AdmonitionTitle;
+
+impl NodeValue for AdmonitionTitle {
+  fn render(&self, node: &Node, fmt: &mut dyn Renderer) {
+    let mut attrs = node.attrs.clone();
+    attrs.push(("class", "admonition-title".to_string()));
+
+    fmt.cr();
+    fmt.open("p", &attrs);
+    fmt.contents(&node.children);
+    fmt.close("p");
+    fmt.cr();
+  }
+}
+
+/// Adds `:::kind [title]` containers and GitHub style `> [!KIND]` alerts.
+///
+/// # Examples
+///
+/// ```
+/// // Input:
+/// // :::warning Be careful
+/// // This is *important*.
+/// // :::
+///
+/// // Rendered output:
+/// // <aside class="admonition warning">
+/// // <p class="admonition-title">Be careful</p>
+/// // <p>This is <em>important</em>.</p>
+/// // </aside>
+/// ```
+pub fn add(md: &mut MarkdownIt) {
+  md.block.add_rule::<AdmonitionScanner>();
+  md.add_rule::<AlertRule>()
+    .after::<InlineParserRule>()
+    .after::<BlockquoteParagraphRule>();
+}
+
+fn default_title(kind: &str) -> Node {
+  let mut title = kind.to_string();
+  title[..1].make_ascii_uppercase();
+
+  let mut node = Node::new(AdmonitionTitle);
+  node.children.push(Node::new(Text { content: title }));
+  node
+}
+
+struct AdmonitionScanner;
+
+impl AdmonitionScanner {
+  fn marker_len(line: &str) -> usize {
+    line.chars().take_while(|c| *c == ':').count()
+  }
+
+  /// Returns `(marker_len, kind, title)` of an opening line such as `:::note title`.
+  fn get_header(line: &str) -> Option<(usize, String, &str)> {
+    let marker_len = Self::marker_len(line);
+    if marker_len < 3 {
+      return None;
+    }
+
+    let params = line[marker_len..].trim_start();
+    let kind = params.split_whitespace().next()?.to_ascii_lowercase();
+    if !KINDS.contains(&kind.as_str()) {
+      return None;
+    }
+
+    let title = params[kind.len()..].trim();
+    Some((marker_len, kind, title))
+  }
+}
+
+impl BlockRule for AdmonitionScanner {
+  fn check(state: &mut BlockState) -> Option<()> {
+    if state.line_indent(state.line) >= state.md.max_indent {
+      return None;
+    }
+    Self::get_header(state.get_line(state.line)).map(|_| ())
+  }
+
+  fn run(state: &mut BlockState) -> Option<(Node, usize)> {
+    if state.line_indent(state.line) >= state.md.max_indent {
+      return None;
+    }
+
+    let line = state.get_line(state.line);
+    let (marker_len, kind, title) = Self::get_header(line)?;
+    let title_node = if title.is_empty() {
+      default_title(&kind)
+    } else {
+      let title_offset = title.as_ptr() as usize - line.as_ptr() as usize;
+      let mapping = vec![(
+        0,
+        state.line_offsets[state.line].first_nonspace + title_offset,
+      )];
+      let mut node = Node::new(AdmonitionTitle);
+      node
+        .children
+        .push(Node::new(InlineRoot::new(title.to_string(), mapping)));
+      node
+    };
+
+    // search the closing marker, which must be at least as long as the opening one
+    let start_line = state.line;
+    let mut next_line = start_line + 1;
+    let mut have_end_marker = false;
+    while next_line < state.line_max {
+      if !state.is_empty(next_line) && state.line_indent(next_line) < 0 {
+        break;
+      }
+      let line = state.get_line(next_line);
+      if state.line_indent(next_line) < state.md.max_indent
+        && Self::marker_len(line) >= marker_len
+        && line.trim_end().chars().all(|c| c == ':')
+      {
+        have_end_marker = true;
+        break;
+      }
+      next_line += 1;
+    }
+
+    let old_node = std::mem::replace(&mut state.node, Node::new(Admonition { kind }));
+    let old_line_max = state.line_max;
+    state.line = start_line + 1;
+    state.line_max = next_line;
+    state.md.block.tokenize(state);
+    state.line = start_line;
+    state.line_max = old_line_max;
+    let mut node = std::mem::replace(&mut state.node, old_node);
+    node.children.insert(0, title_node);
+
+    Some((
+      node,
+      next_line - start_line + if have_end_marker { 1 } else { 0 },
+    ))
+  }
+}
+
+struct AlertRule;
+
+impl CoreRule for AlertRule {
+  fn run(root: &mut Node, _: &MarkdownIt) {
+    root.walk_mut(|node, _| {
+      if !node.is::<Blockquote>() {
+        return;
+      }
+      let Some(kind) = node.children.first_mut().and_then(take_alert_marker) else {
+        return;
+      };
+
+      let mut paragraph = node.children.remove(0);
+      let body = split_at_break(&mut paragraph);
+      let title = if paragraph.collect_text().trim().is_empty() {
+        default_title(&kind)
+      } else {
+        let mut title = Node::new(AdmonitionTitle);
+        title.children = std::mem::take(&mut paragraph.children);
+        title
+      };
+
+      node.children.insert(0, title);
+      if let Some(body) = body {
+        node.children.insert(1, body);
+      }
+      node.replace(Admonition { kind });
+    });
+  }
+}
+
+/// Removes a leading `[!KIND]` from the paragraph and returns the lowercased kind.
+fn take_alert_marker(paragraph: &mut Node) -> Option<String> {
+  if !paragraph.is::<Paragraph>() {
+    return None;
+  }
+  let text = paragraph.children.first_mut()?.cast_mut::<Text>()?;
+  let rest = text.content.strip_prefix("[!")?;
+  let (kind, rest) = rest.split_once(']')?;
+  let kind = kind.to_ascii_lowercase();
+  if !ALERT_KINDS.contains(&kind.as_str()) {
+    return None;
+  }
+
+  text.content = rest.trim_start().to_string();
+  if text.content.is_empty() {
+    paragraph.children.remove(0);
+  }
+  Some(kind)
+}
+
+/// Moves the lines after the first line break into a new paragraph. Without `blockquote_paragraphs`,
+/// the body of an alert is in the same paragraph as the marker.
+fn split_at_break(paragraph: &mut Node) -> Option<Node> {
+  let index = paragraph
+    .children
+    .iter()
+    .position(|child| child.is::<Softbreak>() || child.is::<Hardbreak>())?;
+  let mut rest = paragraph.children.split_off(index);
+  rest.remove(0);
+  if rest.is_empty() {
+    return None;
+  }
+  let mut body = Node::new(Paragraph);
+  body.children = rest;
+  Some(body)
+}
@@ -0,0 +1,117 @@
+use std::collections::HashSet;
+
+use markdown_it::parser::core::CoreRule;
+use markdown_it::plugins::cmark::block::heading::ATXHeading;
+use markdown_it::plugins::cmark::block::lheading::SetextHeader;
+use markdown_it::{MarkdownIt, Node};
+
+const FALLBACK_SLUG: &str = "section";
+
+/// Converts heading text into a slug usable as an `id` attribute.
+///
+/// Unicode letters and digits (including CJK) are kept as-is, ASCII letters are lowercased,
+/// whitespace becomes `-` and any other punctuation is dropped.
+///
+/// # Examples
+///
+/// ```
+/// // "Hello, World!"   -> "hello-world"
+/// // "Rust の 所有権"   -> "rust-の-所有権"
+/// // "「括弧」について" -> "括弧について"
+/// ```
+pub fn slugify(text: &str) -> String {
+  let mut slug = String::with_capacity(text.len());
+
+  for c in text.trim().chars() {
+    if c.is_alphanumeric() || c == '_' {
+      slug.extend(c.to_lowercase());
+    } else if (c.is_whitespace() || c == '-') && !slug.ends_with('-') {
+      slug.push('-');
+    }
+  }
+
+  let slug = slug.trim_matches('-');
+  if slug.is_empty() {
+    FALLBACK_SLUG.to_string()
+  } else {
+    slug.to_string()
+  }
+}
+
+/// Returns `slug` itself, or `slug-N` with the smallest `N` not yet used in the document.
+fn unique_slug(slug: String, used: &mut HashSet<String>) -> String {
+  if used.insert(slug.clone()) {
+    return slug;
+  }
+
+  let mut n = 1;
+  loop {
+    let candidate = format!("{}-{}", slug, n);
+    if used.insert(candidate.clone()) {
+      return candidate;
+    }
+    n += 1;
+  }
+}
+
+pub fn heading_level(node: &Node) -> Option<u8> {
+  if let Some(h) = node.cast::<ATXHeading>() {
+    Some(h.level)
+  } else {
+    node.cast::<SetextHeader>().map(|h| h.level)
+  }
+}
+
+pub fn add(md: &mut MarkdownIt) {
+  md.add_rule::<HeadingAnchorRule>();
+}
+
+struct HeadingAnchorRule;
+
+impl CoreRule for HeadingAnchorRule {
+  fn run(root: &mut Node, _: &MarkdownIt) {
+    let mut used = HashSet::new();
+
+    root.walk_mut(|node, _| {
+      if heading_level(node).is_some() {
+        let id = unique_slug(slugify(&node.collect_text()), &mut used);
+        node.attrs.push(("id", id));
+      }
+    });
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_slugify() {
+    assert_eq!(slugify("Hello, World!"), "hello-world");
+    assert_eq!(slugify("  Leading and trailing  "), "leading-and-trailing");
+    assert_eq!(slugify("snake_case - kebab"), "snake_case-kebab");
+    assert_eq!(slugify("C++ & Rust"), "c-rust");
+  }
+
+  #[test]
+  fn test_slugify_cjk() {
+    assert_eq!(slugify("Rust の 所有権"), "rust-の-所有権");
+    assert_eq!(slugify("「括弧」について"), "括弧について");
+    assert_eq!(slugify("全角　スペース"), "全角-スペース");
+  }
+
+  #[test]
+  fn test_slugify_empty() {
+    assert_eq!(slugify(""), "section");
+    assert_eq!(slugify("!!!"), "section");
+  }
+
+  #[test]
+  fn test_unique_slug() {
+    let mut used = HashSet::new();
+    assert_eq!(unique_slug("foo".to_string(), &mut used), "foo");
+    assert_eq!(unique_slug("foo".to_string(), &mut used), "foo-1");
+    assert_eq!(unique_slug("foo".to_string(), &mut used), "foo-2");
+    assert_eq!(unique_slug("foo-1".to_string(), &mut used), "foo-1-1");
+  }
+}
@@ -0,0 +1,224 @@
+use markdown_it::Node;
+use markdown_it::common::sourcemap::SourceWithLineStarts;
+use markdown_it::parser::core::Root;
+use markdown_it::parser::inline::{Text, TextSpecial};
+use markdown_it::plugins::cmark::block::code::CodeBlock;
+use markdown_it::plugins::cmark::block::fence::CodeFence;
+use markdown_it::plugins::cmark::block::heading::ATXHeading;
+use markdown_it::plugins::cmark::block::lheading::SetextHeader;
+use markdown_it::plugins::cmark::block::list::{BulletList, OrderedList};
+use markdown_it::plugins::cmark::inline::autolink::Autolink;
+use markdown_it::plugins::cmark::inline::image::Image;
+use markdown_it::plugins::cmark::inline::link::Link;
+use markdown_it::plugins::html::html_block::HtmlBlock;
+use markdown_it::plugins::html::html_inline::HtmlInline;
+use markdown_it_footnote::definitions::FootnoteDefinition;
+use markdown_it_footnote::references::FootnoteReference;
+use markdown_it_lazyload::LazyLoadImage;
+use serde::Serialize;
+use serde_json::{Map, Value, json};
+
+use super::abbr::{Abbreviation, AbbreviationDefinition};
+use super::admonition::Admonition;
+use super::embed::Embed;
+use super::fence::HighlightedCode;
+use super::footnote::{FootnoteItem, FootnoteRef};
+use super::image::{Figure, ProcessedImage};
+use super::math::Math;
+use super::ruby::Ruby;
+use super::task_list::TaskCheckbox;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AstNode {
+  /// Name of the node type, e.g. `Paragraph` or `ATXHeading`.
+  #[serde(rename = "type")]
+  pub node_type: String,
+  /// HTML attributes of the node, together with the values of known node types
+  /// (e.g. `level` of headings, `url` of links and `content` of text).
+  pub attrs: Map<String, Value>,
+  /// 1-based first and last line in the markdown source.
+  pub lines: Option<[usize; 2]>,
+  pub children: Vec<AstNode>,
+}
+
+/// Converts the AST into a serializable tree.
+pub fn collect(root: &Node) -> AstNode {
+  let source = root
+    .cast::<Root>()
+    .map(|r| r.content.as_str())
+    .unwrap_or_default();
+  let map = SourceWithLineStarts::new(source);
+  convert(root, &map)
+}
+
+fn convert(node: &Node, map: &SourceWithLineStarts) -> AstNode {
+  let mut attrs = Map::new();
+  for (name, value) in node.attrs.iter() {
+    // repeated attributes (e.g. `class`) are joined like in the rendered HTML
+    match attrs.get_mut(*name) {
+      Some(Value::String(existing)) => {
+        existing.push(' ');
+        existing.push_str(value);
+      }
+      _ => {
+        attrs.insert(name.to_string(), json!(value));
+      }
+    }
+  }
+  attrs.extend(values(node));
+
+  AstNode {
+    node_type: type_name(node.name()).to_string(),
+    attrs,
+    lines: node.srcmap.map(|pos| {
+      let ((start, _), (end, _)) = pos.get_positions(map);
+      [start as usize, end as usize]
+    }),
+    children: node
+      .children
+      .iter()
+      .map(|child| convert(child, map))
+      .collect(),
+  }
+}
+
+/// `markdown_it::plugins::cmark::block::paragraph::Paragraph` -> `Paragraph`
+fn type_name(name: &str) -> &str {
+  let name = name.split('<').next().unwrap_or(name);
+  name.rsplit("::").next().unwrap_or(name)
+}
+
+/// Values of known node types.
+fn values(node: &Node) -> Map<String, Value> {
+  let value = if let Some(n) = node.cast::<Text>() {
+    json!({ "content": n.content })
+  } else if let Some(n) = node.cast::<TextSpecial>() {
+    json!({ "content": n.content, "markup": n.markup, "info": n.info })
+  } else if let Some(n) = node.cast::<ATXHeading>() {
+    json!({ "level": n.level })
+  } else if let Some(n) = node.cast::<SetextHeader>() {
+    json!({ "level": n.level })
+  } else if let Some(n) = node.cast::<OrderedList>() {
+    json!({ "start": n.start, "marker": n.marker })
+  } else if let Some(n) = node.cast::<BulletList>() {
+    json!({ "marker": n.marker })
+  } else if let Some(n) = node.cast::<Link>() {
+    json!({ "url": n.url, "title": n.title })
+  } else if let Some(n) = node.cast::<Autolink>() {
+    json!({ "url": n.url })
+  } else if let Some(n) = node.cast::<Image>() {
+    json!({ "url": n.url, "title": n.title })
+  } else if let Some(n) = node.cast::<LazyLoadImage>() {
+    json!({ "url": n.cmark_image.url, "title": n.cmark_image.title })
+  } else if let Some(n) = node.cast::<ProcessedImage>() {
+    json!({ "url": n.url, "title": n.title, "width": n.width, "height": n.height, "srcset": n.srcset })
+  } else if let Some(n) = node.cast::<Figure>() {
+    json!({ "caption": n.caption })
+  } else if let Some(n) = node.cast::<CodeFence>() {
+    json!({ "info": n.info, "content": n.content })
+  } else if let Some(n) = node.cast::<CodeBlock>() {
+    json!({ "content": n.content })
+  } else if let Some(n) = node.cast::<HighlightedCode>() {
+    json!({ "lang": n.lang, "content": n.content })
+  } else if let Some(n) = node.cast::<HtmlBlock>() {
+    json!({ "content": n.content })
+  } else if let Some(n) = node.cast::<HtmlInline>() {
+    json!({ "content": n.content })
+  } else if let Some(n) = node.cast::<Math>() {
+    json!({ "latex": n.latex, "display": n.display, "error": n.result.as_ref().err() })
+  } else if let Some(n) = node.cast::<Admonition>() {
+    json!({ "kind": n.kind })
+  } else if let Some(n) = node.cast::<Ruby>() {
+    json!({ "pairs": n.pairs })
+  } else if let Some(n) = node.cast::<Embed>() {
+    json!({ "provider": n.provider.name, "id": n.id })
+  } else if let Some(n) = node.cast::<TaskCheckbox>() {
+    json!({ "checked": n.checked })
+  } else if let Some(n) = node.cast::<Abbreviation>() {
+    json!({ "title": n.title })
+  } else if let Some(n) = node.cast::<AbbreviationDefinition>() {
+    json!({ "label": n.label, "title": n.title })
+  } else if let Some(n) = node.cast::<FootnoteReference>() {
+    json!({ "label": n.label })
+  } else if let Some(n) = node.cast::<FootnoteDefinition>() {
+    json!({ "label": n.label })
+  } else if let Some(n) = node.cast::<FootnoteRef>() {
+    json!({ "label": n.label })
+  } else if let Some(n) = node.cast::<FootnoteItem>() {
+    json!({ "label": n.label })
+  } else {
+    return Map::new();
+  };
+
+  match value {
+    Value::Object(map) => map,
+    _ => Map::new(),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use markdown_it::MarkdownIt;
+
+  fn parse(input: &str) -> AstNode {
+    let mut md = MarkdownIt::new();
+    markdown_it::plugins::cmark::add(&mut md);
+    collect(&md.parse(input))
+  }
+
+  #[test]
+  fn test_collect() {
+    let ast = parse("# Title\n\nSome [link](/a \"A\").");
+
+    assert_eq!(ast.node_type, "Root");
+    assert_eq!(ast.lines, Some([1, 3]));
+
+    let heading = &ast.children[0];
+    assert_eq!(heading.node_type, "ATXHeading");
+    assert_eq!(heading.attrs["level"], json!(1));
+    assert_eq!(heading.lines, Some([1, 1]));
+    assert_eq!(heading.children[0].attrs["content"], json!("Title"));
+
+    let link = &ast.children[1].children[1];
+    assert_eq!(link.node_type, "Link");
+    assert_eq!(link.attrs["url"], json!("/a"));
+    assert_eq!(link.attrs["title"], json!("A"));
+    assert_eq!(link.lines, Some([3, 3]));
+  }
+
+  #[test]
+  fn test_serialize() {
+    let ast = parse("text");
+    let json = serde_json::to_value(&ast).unwrap();
+
+    assert_eq!(
+      json,
+      json!({
+        "type": "Root",
+        "attrs": {},
+        "lines": [1, 1],
+        "children": [{
+          "type": "Paragraph",
+          "attrs": {},
+          "lines": [1, 1],
+          "children": [{
+            "type": "Text",
+            "attrs": { "content": "text" },
+            "lines": [1, 1],
+            "children": [],
+          }],
+        }],
+      })
+    );
+  }
+
+  #[test]
+  fn test_type_name() {
+    assert_eq!(
+      type_name("markdown_it::parser::inline::builtin::skip_text::Text"),
+      "Text"
+    );
+    assert_eq!(type_name("crate::a::Generic<'a', 'b'>"), "Generic");
+  }
+}
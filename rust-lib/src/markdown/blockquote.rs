@@ -0,0 +1,110 @@
+use markdown_it::common::sourcemap::SourcePos;
+use markdown_it::parser::core::{CoreRule, Root};
+use markdown_it::parser::inline::builtin::InlineParserRule;
+use markdown_it::plugins::cmark::block::blockquote::Blockquote;
+use markdown_it::plugins::cmark::block::paragraph::Paragraph;
+use markdown_it::plugins::cmark::inline::newline::Softbreak;
+use markdown_it::{MarkdownIt, Node};
+
+/// Renders consecutive blockquote lines as separate paragraphs.
+///
+/// Only paragraphs that are direct children of a blockquote are split, and only at
+/// softbreaks whose next line starts with its own `>` marker. Lazy continuation lines,
+/// code fences, lists and nested blockquotes are left untouched.
+///
+/// # Examples
+///
+/// ```
+/// // Input:
+/// // > Line 1
+/// // > Line 2
+///
+/// // Rendered output:
+/// // <blockquote>
+/// // <p>Line 1</p>
+/// // <p>Line 2</p>
+/// // </blockquote>
+/// ```
+pub fn add(md: &mut MarkdownIt) {
+  md.add_rule::<BlockquoteParagraphRule>()
+    .after::<InlineParserRule>();
+}
+
+pub struct BlockquoteParagraphRule;
+
+impl CoreRule for BlockquoteParagraphRule {
+  fn run(root: &mut Node, _: &MarkdownIt) {
+    let source = root.cast::<Root>().unwrap().content.clone();
+    split_recursive(root, &source, 0);
+  }
+}
+
+fn split_recursive(node: &mut Node, source: &str, quote_depth: usize) {
+  let is_blockquote = node.is::<Blockquote>();
+  let quote_depth = if is_blockquote {
+    quote_depth + 1
+  } else {
+    quote_depth
+  };
+
+  let children = std::mem::take(&mut node.children);
+  for mut child in children {
+    if is_blockquote && child.is::<Paragraph>() {
+      node
+        .children
+        .extend(split_paragraph(child, source, quote_depth));
+    } else {
+      split_recursive(&mut child, source, quote_depth);
+      node.children.push(child);
+    }
+  }
+}
+
+fn split_paragraph(mut paragraph: Node, source: &str, quote_depth: usize) -> Vec<Node> {
+  let mut result = Vec::new();
+  let mut current = Vec::new();
+
+  for child in std::mem::take(&mut paragraph.children) {
+    if child.is::<Softbreak>() && starts_quoted_line(&child, source, quote_depth) {
+      result.push(new_paragraph(std::mem::take(&mut current)));
+    } else {
+      current.push(child);
+    }
+  }
+
+  if result.is_empty() {
+    paragraph.children = current;
+    return vec![paragraph];
+  }
+
+  result.push(new_paragraph(current));
+  result
+}
+
+fn new_paragraph(children: Vec<Node>) -> Node {
+  let mut node = Node::new(Paragraph);
+  let start = children.first().and_then(|n| n.srcmap);
+  let end = children.last().and_then(|n| n.srcmap);
+  if let (Some(start), Some(end)) = (start, end) {
+    node.srcmap = Some(SourcePos::new(
+      start.get_byte_offsets().0,
+      end.get_byte_offsets().1,
+    ));
+  }
+  node.children = children;
+  node
+}
+
+/// Returns `true` if the line following the softbreak has all `quote_depth` markers,
+/// i.e. it is not a lazy continuation line.
+fn starts_quoted_line(softbreak: &Node, source: &str, quote_depth: usize) -> bool {
+  let Some(srcmap) = softbreak.srcmap else {
+    return false;
+  };
+  let (_, next_line_pos) = srcmap.get_byte_offsets();
+  let Some(prefix) = source.get(..next_line_pos) else {
+    return false;
+  };
+  let line_start = prefix.rfind('\n').map(|i| i + 1).unwrap_or(0);
+  prefix[line_start..].matches('>').count() >= quote_depth
+}
@@ -0,0 +1,234 @@
+use markdown_it::parser::core::CoreRule;
+use markdown_it::parser::inline::builtin::InlineParserRule;
+use markdown_it::parser::inline::{Text, TextSpecial};
+use markdown_it::plugins::cmark::inline::newline::Softbreak;
+use markdown_it::plugins::extra::smartquotes::SmartQuotesRule;
+use markdown_it::plugins::extra::typographer::TypographerRule;
+use markdown_it::{MarkdownIt, Node};
+
+use super::blockquote::BlockquoteParagraphRule;
+use super::quotes::CustomSmartQuotesRule;
+
+/// Returns `true` for ideographs, kana and hangul.
+///
+/// CJK symbols and full-width punctuation (e.g. `、` `。` `「`) are not included.
+pub fn is_cjk(c: char) -> bool {
+  matches!(c,
+    '\u{1100}'..='\u{11FF}'     // Hangul Jamo
+    | '\u{3040}'..='\u{309F}'   // Hiragana
+    | '\u{30A0}'..='\u{30FF}'   // Katakana
+    | '\u{31F0}'..='\u{31FF}'   // Katakana Phonetic Extensions
+    | '\u{3400}'..='\u{4DBF}'   // CJK Unified Ideographs Extension A
+    | '\u{4E00}'..='\u{9FFF}'   // CJK Unified Ideographs
+    | '\u{AC00}'..='\u{D7AF}'   // Hangul Syllables
+    | '\u{F900}'..='\u{FAFF}'   // CJK Compatibility Ideographs
+    | '\u{FF66}'..='\u{FF9F}'   // Halfwidth Katakana
+    | '\u{20000}'..='\u{2FFFF}' // CJK Unified Ideographs Extension B and later
+  )
+}
+
+/// Returns `true` for full-width punctuation such as `、` `。` `「` `（` `！`.
+pub fn is_fullwidth_punctuation(c: char) -> bool {
+  matches!(c,
+    '\u{3000}'..='\u{303F}'   // CJK Symbols and Punctuation
+    | '\u{FF01}'..='\u{FF60}' // Fullwidth ASCII variants
+    | '\u{FFE0}'..='\u{FFE6}' // Fullwidth symbol variants
+  )
+}
+
+fn is_wide(c: char) -> bool {
+  is_cjk(c) || is_fullwidth_punctuation(c)
+}
+
+/// Adds CJK aware text handling:
+///
+/// - softbreaks between CJK characters are removed instead of being rendered as a space
+///   (same as markdown-it-cjk-breaks)
+/// - if `keep_quotes` is set, quotes next to CJK characters or full-width punctuation are
+///   left as typed, since the default smartquotes (`“”‘’`) are designed for English
+///
+/// # Examples
+///
+/// ```
+/// // Input:
+/// // 日本語の
+/// // 文章です。「"Rust"」
+///
+/// // Rendered output:
+/// // <p>日本語の文章です。「&quot;Rust&quot;」</p>
+/// ```
+pub fn add(md: &mut MarkdownIt, keep_quotes: bool) {
+  md.add_rule::<CjkBreakRule>()
+    .after::<InlineParserRule>()
+    .after::<BlockquoteParagraphRule>();
+  if !keep_quotes {
+    return;
+  }
+  md.add_rule::<CjkQuoteRule>()
+    .after::<InlineParserRule>()
+    .before::<SmartQuotesRule<'‘', '’', '“', '”'>>()
+    .before::<CustomSmartQuotesRule>()
+    .before::<TypographerRule>();
+}
+
+struct CjkBreakRule;
+
+impl CoreRule for CjkBreakRule {
+  fn run(root: &mut Node, _: &MarkdownIt) {
+    root.walk_mut(|node, _| {
+      let mut i = 1;
+      while i + 1 < node.children.len() {
+        let children = &node.children;
+        if children[i].is::<Softbreak>()
+          && last_char(&children[i - 1]).is_some_and(is_wide)
+          && first_char(&children[i + 1]).is_some_and(is_wide)
+        {
+          node.children.remove(i);
+        } else {
+          i += 1;
+        }
+      }
+    });
+  }
+}
+
+fn last_char(node: &Node) -> Option<char> {
+  if let Some(text) = node.cast::<Text>() {
+    return text.content.chars().last();
+  }
+  if let Some(text) = node.cast::<TextSpecial>() {
+    return text.content.chars().last();
+  }
+  node.children.last().and_then(last_char)
+}
+
+fn first_char(node: &Node) -> Option<char> {
+  if let Some(text) = node.cast::<Text>() {
+    return text.content.chars().next();
+  }
+  if let Some(text) = node.cast::<TextSpecial>() {
+    return text.content.chars().next();
+  }
+  node.children.first().and_then(first_char)
+}
+
+struct CjkQuoteRule;
+
+impl CoreRule for CjkQuoteRule {
+  fn run(root: &mut Node, _: &MarkdownIt) {
+    root.walk_mut(|node, _| {
+      if !node.children.iter().any(has_cjk_quote) {
+        return;
+      }
+      let children = std::mem::take(&mut node.children);
+      for child in children {
+        if has_cjk_quote(&child) {
+          node.children.extend(split_quotes(child));
+        } else {
+          node.children.push(child);
+        }
+      }
+    });
+  }
+}
+
+fn has_cjk_quote(node: &Node) -> bool {
+  node
+    .cast::<Text>()
+    .is_some_and(|text| !cjk_quote_positions(&text.content).is_empty())
+}
+
+/// Byte positions of quotes which have a CJK character or full-width punctuation next to them.
+fn cjk_quote_positions(content: &str) -> Vec<usize> {
+  let chars: Vec<(usize, char)> = content.char_indices().collect();
+  chars
+    .iter()
+    .enumerate()
+    .filter(|(i, (_, c))| {
+      matches!(c, '"' | '\'')
+        && ((*i > 0 && is_wide(chars[i - 1].1))
+          || chars.get(i + 1).is_some_and(|(_, next)| is_wide(*next)))
+    })
+    .map(|(_, (pos, _))| *pos)
+    .collect()
+}
+
+/// Moves the quotes into `TextSpecial` nodes, which smartquotes does not touch.
+fn split_quotes(node: Node) -> Vec<Node> {
+  let content = &node.cast::<Text>().unwrap().content;
+  let mut result = Vec::new();
+  let mut start = 0;
+
+  for pos in cjk_quote_positions(content) {
+    if start < pos {
+      result.push(text_node(
+        &node,
+        Text {
+          content: content[start..pos].to_string(),
+        },
+      ));
+    }
+    let quote = content[pos..pos + 1].to_string();
+    result.push(text_node(
+      &node,
+      TextSpecial {
+        content: quote.clone(),
+        markup: quote,
+        info: "cjk",
+      },
+    ));
+    start = pos + 1;
+  }
+  if start < content.len() {
+    result.push(text_node(
+      &node,
+      Text {
+        content: content[start..].to_string(),
+      },
+    ));
+  }
+  result
+}
+
+fn text_node<T: markdown_it::NodeValue>(original: &Node, value: T) -> Node {
+  let mut node = Node::new(value);
+  node.srcmap = original.srcmap;
+  node
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_is_cjk() {
+    assert!(is_cjk('漢'));
+    assert!(is_cjk('か'));
+    assert!(is_cjk('カ'));
+    assert!(is_cjk('ｶ'));
+    assert!(is_cjk('한'));
+    assert!(!is_cjk('a'));
+    assert!(!is_cjk('1'));
+    assert!(!is_cjk('。'));
+    assert!(!is_cjk('「'));
+  }
+
+  #[test]
+  fn test_is_fullwidth_punctuation() {
+    assert!(is_fullwidth_punctuation('。'));
+    assert!(is_fullwidth_punctuation('「'));
+    assert!(is_fullwidth_punctuation('（'));
+    assert!(is_fullwidth_punctuation('！'));
+    assert!(!is_fullwidth_punctuation('!'));
+    assert!(!is_fullwidth_punctuation('漢'));
+  }
+
+  #[test]
+  fn test_cjk_quote_positions() {
+    assert_eq!(cjk_quote_positions("日本語'テスト'です"), vec![9, 19]);
+    assert_eq!(
+      cjk_quote_positions("\"Hello\" 'world'"),
+      Vec::<usize>::new()
+    );
+  }
+}
@@ -0,0 +1,273 @@
+use markdown_it::parser::block::{BlockRule, BlockState};
+use markdown_it::parser::inline::InlineRoot;
+use markdown_it::plugins::cmark::block::paragraph::Paragraph;
+use markdown_it::{MarkdownIt, Node, NodeValue, Renderer};
+
+#[derive(Debug)]
+pub struct DefinitionList;
+
+impl NodeValue for DefinitionList {
+  fn render(&self, node: &Node, fmt: &mut dyn Renderer) {
+    fmt.cr();
+    fmt.open("dl", &node.attrs);
+    fmt.cr();
+    fmt.contents(&node.children);
+    fmt.cr();
+    fmt.close("dl");
+    fmt.cr();
+  }
+}
+
+#[derive(Debug)]
+pub struct DefinitionTerm;
+
+impl NodeValue for DefinitionTerm {
+  fn render(&self, node: &Node, fmt: &mut dyn Renderer) {
+    fmt.open("dt", &node.attrs);
+    fmt.contents(&node.children);
+    fmt.close("dt");
+    fmt.cr();
+  }
+}
+
+#[derive(Debug)]
+pub struct DefinitionDetails;
+
+impl NodeValue for DefinitionDetails {
+  fn render(&self, node: &Node, fmt: &mut dyn Renderer) {
+    fmt.open("dd", &node.attrs);
+    fmt.contents(&node.children);
+    fmt.close("dd");
+    fmt.cr();
+  }
+}
+
+/// Adds definition lists with the same syntax and output as markdown-it-deflist.
+///
+/// # Examples
+///
+/// ```
+/// // Input:
+/// // Term
+/// // : Definition
+///
+/// // Rendered output:
+/// // <dl>
+/// // <dt>Term</dt>
+/// // <dd>Definition</dd>
+/// // </dl>
+/// ```
+pub fn add(md: &mut MarkdownIt) {
+  md.block.add_rule::<DefinitionListScanner>();
+}
+
+struct DefinitionListScanner;
+
+impl DefinitionListScanner {
+  /// Returns the position after `:` or `~` when the line is a definition.
+  fn skip_marker(state: &BlockState, line: usize) -> Option<usize> {
+    if line >= state.line_max {
+      return None;
+    }
+    let offsets = &state.line_offsets[line];
+    let src = &state.src[offsets.first_nonspace..offsets.line_end];
+    let rest = src.strip_prefix([':', '~'])?;
+    let content = rest.trim_start_matches([' ', '\t']);
+    // a space after the marker is required, and the definition can't be empty
+    if content.len() == rest.len() || content.is_empty() {
+      return None;
+    }
+    Some(offsets.first_nonspace + 1)
+  }
+
+  /// Returns the line of the first definition of a term at `line`.
+  fn find_definition(state: &BlockState, line: usize) -> Option<usize> {
+    let mut dd_line = line + 1;
+    if state.is_empty(dd_line) {
+      dd_line += 1;
+    }
+    if dd_line >= state.line_max || state.line_indent(dd_line) < 0 {
+      return None;
+    }
+    Self::skip_marker(state, dd_line).map(|_| dd_line)
+  }
+
+  fn mark_tight_paragraphs(nodes: &mut Vec<Node>) {
+    let mut idx = 0;
+    while idx < nodes.len() {
+      if nodes[idx].is::<Paragraph>() {
+        let children = std::mem::take(&mut nodes[idx].children);
+        let len = children.len();
+        nodes.splice(idx..idx + 1, children);
+        idx += len;
+      } else {
+        idx += 1;
+      }
+    }
+  }
+}
+
+impl BlockRule for DefinitionListScanner {
+  fn check(state: &mut BlockState) -> Option<()> {
+    // only the next definition of the same term can interrupt a paragraph
+    if !state.node.is::<DefinitionDetails>() {
+      return None;
+    }
+    Self::skip_marker(state, state.line).map(|_| ())
+  }
+
+  fn run(state: &mut BlockState) -> Option<(Node, usize)> {
+    let start_line = state.line;
+    let mut dd_line = Self::find_definition(state, start_line)?;
+
+    let old_node = std::mem::replace(&mut state.node, Node::new(DefinitionList));
+    let mut tight = true;
+    let mut dt_line = start_line;
+    let mut next_line;
+
+    'outer: loop {
+      // a blank line between the term and its definition makes the list loose
+      if dd_line > dt_line + 1 {
+        tight = false;
+      }
+
+      let offsets = &state.line_offsets[dt_line];
+      let content = state.get_line(dt_line).trim_end().to_owned();
+      let mut term = Node::new(DefinitionTerm);
+      term.srcmap = state.get_map(dt_line, dt_line);
+      term.children.push(Node::new(InlineRoot::new(
+        content,
+        vec![(0, offsets.first_nonspace)],
+      )));
+      state.node.children.push(term);
+
+      let mut prev_empty_end = false;
+      loop {
+        let old_offsets = state.line_offsets[dd_line].clone();
+        let marker_end = Self::skip_marker(state, dd_line).unwrap();
+        let rest = &state.src[marker_end..old_offsets.line_end];
+        let content_start = marker_end + rest.len() - rest.trim_start_matches([' ', '\t']).len();
+
+        // column of the definition content, taking tabs into account
+        let mut column = old_offsets.indent_nonspace as usize + 1;
+        for c in rest[..content_start - marker_end].chars() {
+          column += if c == '\t' { 4 - column % 4 } else { 1 };
+        }
+
+        let old_node = std::mem::replace(&mut state.node, Node::new(DefinitionDetails));
+        let old_blk_indent = state.blk_indent;
+        let old_tight = state.tight;
+
+        state.blk_indent = old_offsets.indent_nonspace as usize + 2;
+        state.line_offsets[dd_line].first_nonspace = content_start;
+        state.line_offsets[dd_line].indent_nonspace = column as i32;
+        state.tight = true;
+        state.line = dd_line;
+        state.md.block.tokenize(state);
+
+        if !state.tight || prev_empty_end {
+          tight = false;
+        }
+        prev_empty_end = (state.line - dd_line) > 1 && state.is_empty(state.line - 1);
+
+        state.blk_indent = old_blk_indent;
+        state.line_offsets[dd_line] = old_offsets;
+        state.tight = old_tight;
+
+        next_line = state.line;
+        let mut node = std::mem::replace(&mut state.node, old_node);
+        node.srcmap = state.get_map(dd_line, next_line - 1);
+        state.node.children.push(node);
+
+        if next_line >= state.line_max || state.line_indent(next_line) < 0 {
+          break 'outer;
+        }
+        if Self::skip_marker(state, next_line).is_none() {
+          break;
+        }
+        dd_line = next_line;
+      }
+
+      // another term of the same list
+      dt_line = next_line;
+      if state.is_empty(dt_line) {
+        break;
+      }
+      match Self::find_definition(state, dt_line) {
+        Some(line) => dd_line = line,
+        None => break,
+      }
+    }
+
+    if tight {
+      for child in state.node.children.iter_mut() {
+        if child.is::<DefinitionDetails>() {
+          Self::mark_tight_paragraphs(&mut child.children);
+        }
+      }
+    }
+
+    state.line = start_line;
+    let node = std::mem::replace(&mut state.node, old_node);
+    Some((node, next_line - start_line))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn parser() -> MarkdownIt {
+    let mut md = MarkdownIt::new();
+    markdown_it::plugins::cmark::add(&mut md);
+    add(&mut md);
+    md
+  }
+
+  #[test]
+  fn test_definition_list() {
+    let output = parser()
+      .parse("Term 1\n: Definition 1\n~ Definition 2\n\nTerm 2\n:   *Definition* 3")
+      .render();
+    assert_eq!(
+      output,
+      "<dl>\n<dt>Term 1</dt>\n<dd>Definition 1</dd>\n<dd>Definition 2</dd>\n\
+       <dt>Term 2</dt>\n<dd><em>Definition</em> 3</dd>\n</dl>\n"
+    );
+  }
+
+  #[test]
+  fn test_loose_definition_list() {
+    let output = parser()
+      .parse("Term\n\n: First paragraph\n\n  Second paragraph\n\nAfter")
+      .render();
+    assert_eq!(
+      output,
+      "<dl>\n<dt>Term</dt>\n<dd>\n<p>First paragraph</p>\n<p>Second paragraph</p>\n</dd>\n</dl>\n\
+       <p>After</p>\n"
+    );
+  }
+
+  #[test]
+  fn test_blank_line_after_term() {
+    let output = parser().parse("Term\n\n: Definition").render();
+    assert_eq!(
+      output,
+      "<dl>\n<dt>Term</dt>\n<dd>\n<p>Definition</p>\n</dd>\n</dl>\n"
+    );
+  }
+
+  #[test]
+  fn test_not_definition_list() {
+    let md = parser();
+    assert_eq!(
+      md.parse("Term\n:no space").render(),
+      "<p>Term\n:no space</p>\n"
+    );
+    assert_eq!(md.parse(": alone").render(), "<p>: alone</p>\n");
+    assert_eq!(
+      md.parse("Paragraph\nTerm\n: Definition").render(),
+      "<p>Paragraph\nTerm\n: Definition</p>\n"
+    );
+  }
+}
@@ -0,0 +1,348 @@
+use markdown_it::parser::block::{BlockRule, BlockState};
+use markdown_it::{MarkdownIt, Node, NodeValue, Renderer};
+use url::Url;
+
+/// A site whose urls are turned into embeds.
+#[derive(Debug)]
+pub struct Provider {
+  pub name: &'static str,
+  /// Returns the id of the embedded content (e.g. the video id) when the url belongs to the provider.
+  parse: fn(&Url) -> Option<String>,
+  render: fn(&str, &Node, &mut dyn Renderer),
+}
+
+/// Providers are tried in order, the first one that accepts the url wins.
+static PROVIDERS: &[Provider] = &[
+  Provider {
+    name: "youtube",
+    parse: parse_youtube,
+    render: render_youtube,
+  },
+  Provider {
+    name: "twitter",
+    parse: parse_twitter,
+    render: render_twitter,
+  },
+  Provider {
+    name: "gist",
+    parse: parse_gist,
+    render: render_gist,
+  },
+  Provider {
+    name: "speakerdeck",
+    parse: parse_speakerdeck,
+    render: render_speakerdeck,
+  },
+];
+
+#[derive(Debug)]
+pub struct Embed {
+  pub provider: &'static Provider,
+  pub id: String,
+}
+
+impl NodeValue for Embed {
+  fn render(&self, node: &Node, fmt: &mut dyn Renderer) {
+    fmt.cr();
+    (self.provider.render)(&self.id, node, fmt);
+    fmt.cr();
+  }
+}
+
+/// Turns a bare url of a known provider that is alone in a paragraph into an embed.
+///
+/// Nothing is loaded from the provider until the reader interacts with the embed (or a script
+/// enhances it), and the output only contains links and images, so no `<iframe>` is needed.
+///
+/// # Examples
+///
+/// ```
+/// // Input:
+/// // https://youtu.be/dQw4w9WgXcQ
+///
+/// // Rendered output:
+/// // <div class="embed embed-youtube" data-src="https://www.youtube-nocookie.com/embed/dQw4w9WgXcQ?autoplay=1">
+/// // <a href="https://www.youtube.com/watch?v=dQw4w9WgXcQ"><img src="https://i.ytimg.com/vi/dQw4w9WgXcQ/hqdefault.jpg" alt="YouTube video" loading="lazy"></a></div>
+/// ```
+pub fn add(md: &mut MarkdownIt) {
+  md.block.add_rule::<EmbedScanner>();
+}
+
+/// Returns the provider and id of the content `url` points to.
+fn find(url: &str) -> Option<(&'static Provider, String)> {
+  if url.chars().any(char::is_whitespace) {
+    return None;
+  }
+  let url = Url::parse(url).ok()?;
+  if !matches!(url.scheme(), "http" | "https") {
+    return None;
+  }
+  PROVIDERS
+    .iter()
+    .find_map(|provider| (provider.parse)(&url).map(|id| (provider, id)))
+}
+
+struct EmbedScanner;
+
+impl BlockRule for EmbedScanner {
+  fn check(_: &mut BlockState) -> Option<()> {
+    None // a url following text is a part of the paragraph
+  }
+
+  fn run(state: &mut BlockState) -> Option<(Node, usize)> {
+    if state.line_indent(state.line) >= state.md.max_indent {
+      return None;
+    }
+    // the next line would be a lazy continuation of the paragraph
+    let next_line = state.line + 1;
+    if next_line < state.line_max && !state.is_empty(next_line) {
+      return None;
+    }
+
+    let (provider, id) = find(state.get_line(state.line).trim())?;
+    Some((Node::new(Embed { provider, id }), 1))
+  }
+}
+
+fn host(url: &Url) -> &str {
+  let host = url.host_str().unwrap_or_default();
+  host
+    .strip_prefix("www.")
+    .or_else(|| host.strip_prefix("m."))
+    .or_else(|| host.strip_prefix("mobile."))
+    .unwrap_or(host)
+}
+
+fn segments(url: &Url) -> Vec<&str> {
+  url
+    .path_segments()
+    .map(|segments| segments.filter(|s| !s.is_empty()).collect())
+    .unwrap_or_default()
+}
+
+fn is_id(id: &str) -> bool {
+  !id.is_empty()
+    && id
+      .chars()
+      .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Link to the original content, shown until the embed is enhanced.
+fn render_link(url: &str, fmt: &mut dyn Renderer) {
+  fmt.open("a", &[("href", url.to_string())]);
+  fmt.text(url);
+  fmt.close("a");
+}
+
+/// `youtube.com/watch?v=ID`, `youtube.com/{shorts,embed,live}/ID` and `youtu.be/ID`
+fn parse_youtube(url: &Url) -> Option<String> {
+  let segments = segments(url);
+  let id = match (host(url), segments.as_slice()) {
+    ("youtu.be", [id]) => id.to_string(),
+    ("youtube.com", ["watch"]) => url
+      .query_pairs()
+      .find(|(key, _)| key == "v")
+      .map(|(_, value)| value.to_string())?,
+    ("youtube.com", ["shorts" | "embed" | "live", id]) => id.to_string(),
+    _ => return None,
+  };
+  is_id(&id).then_some(id)
+}
+
+/// Thumbnail facade, replaced with a `youtube-nocookie.com` player by a script.
+fn render_youtube(id: &str, node: &Node, fmt: &mut dyn Renderer) {
+  let mut attrs = node.attrs.clone();
+  attrs.push(("class", "embed embed-youtube".to_string()));
+  attrs.push((
+    "data-src",
+    format!("https://www.youtube-nocookie.com/embed/{}?autoplay=1", id),
+  ));
+  fmt.open("div", &attrs);
+  fmt.cr();
+  fmt.open(
+    "a",
+    &[("href", format!("https://www.youtube.com/watch?v={}", id))],
+  );
+  fmt.self_close(
+    "img",
+    &[
+      (
+        "src",
+        format!("https://i.ytimg.com/vi/{}/hqdefault.jpg", id),
+      ),
+      ("alt", "YouTube video".to_string()),
+      ("loading", "lazy".to_string()),
+    ],
+  );
+  fmt.close("a");
+  fmt.close("div");
+}
+
+/// `twitter.com/{user}/status/{id}` and `x.com/{user}/status/{id}`
+fn parse_twitter(url: &Url) -> Option<String> {
+  if !matches!(host(url), "twitter.com" | "x.com") {
+    return None;
+  }
+  match segments(url).as_slice() {
+    [user, "status", id] if is_id(user) && id.chars().all(|c| c.is_ascii_digit()) => {
+      Some(format!("{}/status/{}", user, id))
+    }
+    _ => None,
+  }
+}
+
+/// Blockquote in the markup of the twitter widget, without loading the widget.
+fn render_twitter(id: &str, node: &Node, fmt: &mut dyn Renderer) {
+  let mut attrs = node.attrs.clone();
+  attrs.push(("class", "embed embed-twitter twitter-tweet".to_string()));
+  attrs.push(("data-dnt", "true".to_string()));
+  fmt.open("blockquote", &attrs);
+  fmt.cr();
+  fmt.open("p", &[]);
+  render_link(&format!("https://twitter.com/{}", id), fmt);
+  fmt.close("p");
+  fmt.cr();
+  fmt.close("blockquote");
+}
+
+/// `gist.github.com/{user}/{id}` and `gist.github.com/{id}`
+fn parse_gist(url: &Url) -> Option<String> {
+  if host(url) != "gist.github.com" {
+    return None;
+  }
+  match segments(url).as_slice() {
+    [user, id] if is_id(user) && id.chars().all(|c| c.is_ascii_hexdigit()) => {
+      Some(format!("{}/{}", user, id))
+    }
+    [id] if id.chars().all(|c| c.is_ascii_hexdigit()) => Some(id.to_string()),
+    _ => None,
+  }
+}
+
+/// Placeholder for the gist script, which can't be run in place as it uses `document.write`.
+fn render_gist(id: &str, node: &Node, fmt: &mut dyn Renderer) {
+  let url = format!("https://gist.github.com/{}", id);
+  let mut attrs = node.attrs.clone();
+  attrs.push(("class", "embed embed-gist".to_string()));
+  attrs.push(("data-src", format!("{}.js", url)));
+  fmt.open("div", &attrs);
+  render_link(&url, fmt);
+  fmt.close("div");
+}
+
+/// `speakerdeck.com/{user}/{slug}`
+fn parse_speakerdeck(url: &Url) -> Option<String> {
+  if host(url) != "speakerdeck.com" {
+    return None;
+  }
+  match segments(url).as_slice() {
+    [user, slug] if is_id(user) && is_id(slug) => Some(format!("{}/{}", user, slug)),
+    _ => None,
+  }
+}
+
+/// Placeholder resolved with the SpeakerDeck oEmbed endpoint by a script.
+fn render_speakerdeck(id: &str, node: &Node, fmt: &mut dyn Renderer) {
+  let url = format!("https://speakerdeck.com/{}", id);
+  let mut attrs = node.attrs.clone();
+  attrs.push(("class", "embed embed-speakerdeck".to_string()));
+  attrs.push(("data-url", url.clone()));
+  fmt.open("div", &attrs);
+  render_link(&url, fmt);
+  fmt.close("div");
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn render(input: &str) -> String {
+    let mut md = MarkdownIt::new();
+    markdown_it::plugins::cmark::add(&mut md);
+    add(&mut md);
+    md.parse(input).render()
+  }
+
+  fn id(url: &str) -> Option<(&'static str, String)> {
+    find(url).map(|(provider, id)| (provider.name, id))
+  }
+
+  #[test]
+  fn test_youtube() {
+    let expected = "<div class=\"embed embed-youtube\" data-src=\"https://www.youtube-nocookie.com/embed/dQw4w9WgXcQ?autoplay=1\">\n\
+                    <a href=\"https://www.youtube.com/watch?v=dQw4w9WgXcQ\"><img src=\"https://i.ytimg.com/vi/dQw4w9WgXcQ/hqdefault.jpg\" alt=\"YouTube video\" loading=\"lazy\"></a></div>\n";
+    assert_eq!(render("https://youtu.be/dQw4w9WgXcQ"), expected);
+    assert_eq!(
+      render("  https://www.youtube.com/watch?v=dQw4w9WgXcQ&t=42  "),
+      expected
+    );
+
+    assert_eq!(
+      id("https://m.youtube.com/shorts/abc_-1"),
+      Some(("youtube", "abc_-1".to_string()))
+    );
+    assert_eq!(id("https://www.youtube.com/watch?v=a%22b"), None);
+    assert_eq!(id("https://www.youtube.com/channel/abc"), None);
+  }
+
+  #[test]
+  fn test_twitter() {
+    assert_eq!(
+      render("https://x.com/jack/status/20"),
+      "<blockquote class=\"embed embed-twitter twitter-tweet\" data-dnt=\"true\">\n\
+       <p><a href=\"https://twitter.com/jack/status/20\">https://twitter.com/jack/status/20</a></p>\n\
+       </blockquote>\n"
+    );
+    assert_eq!(id("https://twitter.com/jack"), None);
+    assert_eq!(id("https://twitter.com/jack/status/abc"), None);
+  }
+
+  #[test]
+  fn test_gist_and_speakerdeck() {
+    assert_eq!(
+      render("https://gist.github.com/octocat/6cad326836d38bd3a7ae"),
+      "<div class=\"embed embed-gist\" data-src=\"https://gist.github.com/octocat/6cad326836d38bd3a7ae.js\">\
+       <a href=\"https://gist.github.com/octocat/6cad326836d38bd3a7ae\">https://gist.github.com/octocat/6cad326836d38bd3a7ae</a></div>\n"
+    );
+    assert_eq!(
+      render("https://speakerdeck.com/user/my-talk"),
+      "<div class=\"embed embed-speakerdeck\" data-url=\"https://speakerdeck.com/user/my-talk\">\
+       <a href=\"https://speakerdeck.com/user/my-talk\">https://speakerdeck.com/user/my-talk</a></div>\n"
+    );
+  }
+
+  #[test]
+  fn test_not_embed() {
+    // urls within text
+    assert_eq!(
+      render("Watch https://youtu.be/abc"),
+      "<p>Watch https://youtu.be/abc</p>\n"
+    );
+    assert_eq!(
+      render("text\nhttps://youtu.be/abc"),
+      "<p>text\nhttps://youtu.be/abc</p>\n"
+    );
+    assert_eq!(
+      render("https://youtu.be/abc\ntext"),
+      "<p>https://youtu.be/abc\ntext</p>\n"
+    );
+    // unknown providers, other schemes and indented code
+    assert_eq!(
+      render("https://example.com/a"),
+      "<p>https://example.com/a</p>\n"
+    );
+    assert_eq!(id("ftp://youtu.be/abc"), None);
+    assert_eq!(
+      render("    https://youtu.be/abc"),
+      "<pre><code>https://youtu.be/abc\n</code></pre>\n"
+    );
+  }
+
+  #[test]
+  fn test_nested() {
+    assert!(
+      render("- https://youtu.be/abc")
+        .starts_with("<ul>\n<li>\n<div class=\"embed embed-youtube\"")
+    );
+  }
+}
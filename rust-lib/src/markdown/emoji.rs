@@ -0,0 +1,198 @@
+use markdown_it::parser::core::CoreRule;
+use markdown_it::parser::inline::Text;
+use markdown_it::parser::inline::builtin::InlineParserRule;
+use markdown_it::plugins::cmark::inline::autolink::Autolink;
+use markdown_it::plugins::cmark::inline::backticks::CodeInline;
+use markdown_it::plugins::extra::smartquotes::SmartQuotesRule;
+use markdown_it::plugins::extra::typographer::TypographerRule;
+use markdown_it::{MarkdownIt, Node};
+
+use super::abbr::is_word_boundary;
+use super::quotes::CustomSmartQuotesRule;
+
+/// Emoticons replaced by markdown-it-emoji by default.
+const SHORTCUTS: &[(&str, &[&str])] = &[
+  ("angry", &[">:(", ">:-("]),
+  ("blush", &[":\")", ":-\")"]),
+  ("broken_heart", &["</3", "<\\3"]),
+  ("confused", &[":/", ":-/"]),
+  ("cry", &[":'(", ":'-(", ":,(", ":,-("]),
+  ("frowning", &[":(", ":-("]),
+  ("heart", &["<3"]),
+  ("imp", &["]:(", "]:-("]),
+  ("innocent", &["o:)", "O:)", "o:-)", "O:-)", "0:)", "0:-)"]),
+  (
+    "joy",
+    &[":')", ":'-)", ":,)", ":,-)", ":'D", ":'-D", ":,D", ":,-D"],
+  ),
+  ("kissing", &[":*", ":-*"]),
+  ("laughing", &["x-)", "X-)"]),
+  ("neutral_face", &[":|", ":-|"]),
+  ("open_mouth", &[":o", ":-o", ":O", ":-O"]),
+  ("rage", &[":@", ":-@"]),
+  ("smile", &[":D", ":-D"]),
+  ("smiley", &[":)", ":-)"]),
+  ("smiling_imp", &["]:)", "]:-)"]),
+  ("sob", &[":,'(", ":,'-(", ";(", ";-("]),
+  ("stuck_out_tongue", &[":P", ":-P"]),
+  ("sunglasses", &["8-)", "B-)"]),
+  ("sweat", &[",:(", ",:-("]),
+  ("sweat_smile", &[",:)", ",:-)"]),
+  ("unamused", &[":s", ":-S", ":z", ":-Z", ":$", ":-$"]),
+  ("wink", &[";)", ";-)"]),
+];
+
+/// Adds `:shortcode:` emoji and emoticon shortcuts, like markdown-it-emoji.
+///
+/// # Examples
+///
+/// ```
+/// // Input:
+/// // :tada: done :)
+///
+/// // Rendered output:
+/// // <p>🎉 done 😃</p>
+/// ```
+pub fn add(md: &mut MarkdownIt) {
+  md.add_rule::<EmojiRule>()
+    .after::<InlineParserRule>()
+    .before::<SmartQuotesRule<'‘', '’', '“', '”'>>()
+    .before::<CustomSmartQuotesRule>()
+    .before::<TypographerRule>();
+}
+
+struct EmojiRule;
+
+impl CoreRule for EmojiRule {
+  fn run(root: &mut Node, _: &MarkdownIt) {
+    let mut shortcuts: Vec<(&str, &str)> = SHORTCUTS
+      .iter()
+      .filter_map(|(name, shortcuts)| {
+        let emoji = emojis::get_by_shortcode(name)?.as_str();
+        Some(shortcuts.iter().map(move |shortcut| (*shortcut, emoji)))
+      })
+      .flatten()
+      .collect();
+    shortcuts.sort_by_key(|(shortcut, _)| std::cmp::Reverse(shortcut.len()));
+
+    replace_in_children(root, &shortcuts);
+  }
+}
+
+fn replace_in_children(node: &mut Node, shortcuts: &[(&str, &str)]) {
+  for child in node.children.iter_mut() {
+    // the text of autolinks is the url itself
+    if child.is::<Autolink>() || child.is::<CodeInline>() {
+      continue;
+    }
+    if let Some(text) = child.cast_mut::<Text>() {
+      if let Some(content) = replace(&text.content, shortcuts) {
+        text.content = content;
+      }
+    } else {
+      replace_in_children(child, shortcuts);
+    }
+  }
+}
+
+/// Replaces shortcodes and shortcuts in `content`, or returns `None` when there are none.
+fn replace(content: &str, shortcuts: &[(&str, &str)]) -> Option<String> {
+  let mut result = String::new();
+  let mut last = 0;
+  let mut prev = None;
+
+  for (pos, c) in content.char_indices() {
+    let boundary = is_word_boundary(prev);
+    prev = Some(c);
+    if pos < last {
+      continue;
+    }
+    let rest = &content[pos..];
+
+    let found = shortcode(rest).or_else(|| {
+      if !boundary {
+        return None;
+      }
+      shortcuts
+        .iter()
+        .find(|(shortcut, _)| {
+          rest.starts_with(shortcut) && is_word_boundary(rest[shortcut.len()..].chars().next())
+        })
+        .map(|(shortcut, emoji)| (shortcut.len(), *emoji))
+    });
+    let Some((len, emoji)) = found else {
+      continue;
+    };
+
+    result.push_str(&content[last..pos]);
+    result.push_str(emoji);
+    last = pos + len;
+  }
+
+  if last == 0 {
+    return None;
+  }
+  result.push_str(&content[last..]);
+  Some(result)
+}
+
+/// Matches `:name:` at the start of `src` and returns its length and emoji.
+fn shortcode(src: &str) -> Option<(usize, &'static str)> {
+  let rest = src.strip_prefix(':')?;
+  let end = rest.find(':')?;
+  let name = &rest[..end];
+  if name.is_empty()
+    || !name
+      .chars()
+      .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '+' | '-'))
+  {
+    return None;
+  }
+  let emoji = emojis::get_by_shortcode(name)?;
+  Some((end + 2, emoji.as_str()))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn parser() -> MarkdownIt {
+    let mut md = MarkdownIt::new();
+    markdown_it::plugins::cmark::add(&mut md);
+    add(&mut md);
+    md
+  }
+
+  #[test]
+  fn test_shortcode() {
+    let output = parser().parse(":tada: done, *:+1:*").render();
+    assert_eq!(output, "<p>🎉 done, <em>👍</em></p>\n");
+  }
+
+  #[test]
+  fn test_unknown_shortcode() {
+    let output = parser().parse(":not_an_emoji: 12:30:00").render();
+    assert_eq!(output, "<p>:not_an_emoji: 12:30:00</p>\n");
+  }
+
+  #[test]
+  fn test_shortcuts() {
+    let output = parser().parse("Hi :) <3 :-D").render();
+    assert_eq!(output, "<p>Hi 😃 ❤️ 😄</p>\n");
+  }
+
+  #[test]
+  fn test_shortcuts_need_word_boundaries() {
+    let md = parser();
+    assert_eq!(
+      md.parse("https://example.com").render(),
+      "<p>https://example.com</p>\n"
+    );
+    assert_eq!(md.parse("a:)").render(), "<p>a:)</p>\n");
+    assert_eq!(
+      md.parse("<https://example.com/:)>").render(),
+      "<p><a href=\"https://example.com/:)\">https://example.com/:)</a></p>\n"
+    );
+    assert_eq!(md.parse("`:)`").render(), "<p><code>:)</code></p>\n");
+  }
+}
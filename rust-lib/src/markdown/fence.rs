@@ -0,0 +1,317 @@
+use std::sync::LazyLock;
+
+use markdown_it::common::utils::escape_html;
+use markdown_it::parser::core::CoreRule;
+use markdown_it::parser::extset::MarkdownItExt;
+use markdown_it::plugins::cmark::block::code::CodeBlock;
+use markdown_it::plugins::cmark::block::fence::CodeFence;
+use markdown_it::{MarkdownIt, Node, NodeValue, Renderer};
+use regex::Regex;
+
+use crate::highlight::{self, HighlightLayout, HighlightOptions};
+
+static MARK_LINES: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\{\s*\d[\d,\s-]*\}").unwrap());
+
+#[derive(Debug)]
+pub struct HighlightedCode {
+  pub lang: Option<String>,
+  /// The code before highlighting.
+  pub content: String,
+  pub html: String,
+}
+
+impl NodeValue for HighlightedCode {
+  fn render(&self, _: &Node, fmt: &mut dyn Renderer) {
+    fmt.cr();
+    fmt.text_raw(&self.html);
+    fmt.cr();
+  }
+}
+
+/// Rendering options applied to every code block, see `HighlightOptions`.
+#[derive(Debug, Clone)]
+pub struct HighlightSettings {
+  pub class_prefix: Option<String>,
+  pub layout: HighlightLayout,
+  pub gutter: bool,
+  pub copy_button: bool,
+  pub language_label: bool,
+  pub diagram_languages: Option<Vec<String>>,
+}
+
+impl Default for HighlightSettings {
+  fn default() -> Self {
+    let options = HighlightOptions::default();
+    Self {
+      class_prefix: options.class_prefix,
+      layout: options.layout,
+      gutter: options.gutter,
+      copy_button: options.copy_button,
+      language_label: options.language_label,
+      diagram_languages: options.diagram_languages,
+    }
+  }
+}
+
+impl MarkdownItExt for HighlightSettings {}
+
+/// Renders code fences and indented code blocks with `highlight::highlight`.
+///
+/// The fence info string follows the Hexo backtick code block syntax:
+/// `lang [caption [url [link text]]]` and an optional `=N` to set the first line number.
+/// Lines to mark can be added as `{1,4-6}`, see `HighlightOptions::mark_lines`.
+///
+/// # Examples
+///
+/// ```
+/// // Input:
+/// // ```js Example https://example.com source =3
+/// // const x = 1;
+/// // ```
+///
+/// // Rendered output:
+/// // <figure class="highlight js"><figcaption><span>Example</span><a href="https://example.com">source</a></figcaption>
+/// // <table>...<span class="line">3</span>...</table></figure>
+/// ```
+pub fn add(md: &mut MarkdownIt) {
+  md.add_rule::<FenceRule>();
+}
+
+pub fn set_settings(md: &mut MarkdownIt, settings: HighlightSettings) {
+  md.ext.insert(settings);
+}
+
+struct FenceRule;
+
+impl CoreRule for FenceRule {
+  fn run(root: &mut Node, md: &MarkdownIt) {
+    let settings = md
+      .ext
+      .get::<HighlightSettings>()
+      .cloned()
+      .unwrap_or_default();
+
+    root.walk_mut(|node, _| {
+      let (content, options) = if let Some(fence) = node.cast::<CodeFence>() {
+        (&fence.content, parse_info(&fence.info))
+      } else if let Some(block) = node.cast::<CodeBlock>() {
+        (&block.content, HighlightOptions::default())
+      } else {
+        return;
+      };
+      let options = HighlightOptions {
+        class_prefix: settings.class_prefix.clone(),
+        layout: settings.layout,
+        gutter: settings.gutter,
+        copy_button: settings.copy_button,
+        language_label: settings.language_label,
+        diagram_languages: settings.diagram_languages.clone(),
+        ..options
+      };
+      let code = content.strip_suffix('\n').unwrap_or(content).to_string();
+      let lang = options.lang.clone();
+      let html = highlight::highlight(&code, options);
+      node.replace(HighlightedCode {
+        lang,
+        content: code,
+        html,
+      });
+    });
+  }
+}
+
+/// Parses `lang [caption [url [link text]]] [=N]`, with lines to mark as `{1,4-6}` anywhere
+/// (e.g. `js{1,4-6}` or `js {1,4-6} caption`).
+fn parse_info(info: &str) -> HighlightOptions {
+  let (info, mark_lines) = match MARK_LINES.find(info) {
+    Some(m) => (
+      format!("{} {}", &info[..m.start()], &info[m.end()..]),
+      Some(m.as_str().to_string()),
+    ),
+    None => (info.to_string(), None),
+  };
+  let (args, first_line) = match info.split_once('=') {
+    Some((args, n)) => {
+      let n = n.trim().trim_start_matches('+');
+      (args, n.parse().unwrap_or(1))
+    }
+    None => (info.as_str(), 1),
+  };
+
+  let mut words = args.split_whitespace();
+  let lang = words.next().map(str::to_string);
+  let rest: Vec<&str> = words.collect();
+
+  // the url is the first word that looks like a link, except the first word of the caption
+  let url_index = rest
+    .iter()
+    .skip(1)
+    .position(|w| w.starts_with("http://") || w.starts_with("https://") || w.starts_with('/'))
+    .map(|i| i + 1);
+
+  let caption = match url_index {
+    Some(i) => {
+      let link_text = if rest.len() > i + 1 {
+        rest[i + 1..].join(" ")
+      } else {
+        "link".to_string()
+      };
+      Some(format!(
+        "<span>{}</span><a href=\"{}\">{}</a>",
+        escape_html(&rest[..i].join(" ")),
+        escape_html(rest[i]),
+        escape_html(&link_text)
+      ))
+    }
+    None if !rest.is_empty() => Some(format!("<span>{}</span>", escape_html(&rest.join(" ")))),
+    None => None,
+  };
+
+  HighlightOptions {
+    lang,
+    caption,
+    first_line,
+    mark_lines,
+    ..Default::default()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_parse_info_lang_only() {
+    let options = parse_info("rust");
+    assert_eq!(options.lang.as_deref(), Some("rust"));
+    assert_eq!(options.caption, None);
+    assert_eq!(options.first_line, 1);
+  }
+
+  #[test]
+  fn test_parse_info_caption() {
+    let options = parse_info("js Hello world");
+    assert_eq!(options.caption.as_deref(), Some("<span>Hello world</span>"));
+  }
+
+  #[test]
+  fn test_parse_info_caption_with_url() {
+    let options = parse_info("js Array.map https://example.com/map");
+    assert_eq!(
+      options.caption.as_deref(),
+      Some("<span>Array.map</span><a href=\"https://example.com/map\">link</a>")
+    );
+
+    let options = parse_info("js Array map /docs/map MDN docs");
+    assert_eq!(
+      options.caption.as_deref(),
+      Some("<span>Array map</span><a href=\"/docs/map\">MDN docs</a>")
+    );
+  }
+
+  #[test]
+  fn test_parse_info_first_line() {
+    assert_eq!(parse_info("js=5").first_line, 5);
+    assert_eq!(parse_info("js caption =+12").first_line, 12);
+    assert_eq!(
+      parse_info("js caption =+12").caption.as_deref(),
+      Some("<span>caption</span>")
+    );
+    assert_eq!(parse_info("js=").first_line, 1);
+  }
+
+  #[test]
+  fn test_parse_info_mark_lines() {
+    let options = parse_info("js{1,4-6} Example =3");
+    assert_eq!(options.lang.as_deref(), Some("js"));
+    assert_eq!(options.mark_lines.as_deref(), Some("{1,4-6}"));
+    assert_eq!(options.caption.as_deref(), Some("<span>Example</span>"));
+    assert_eq!(options.first_line, 3);
+
+    let options = parse_info("js {2} Example");
+    assert_eq!(options.lang.as_deref(), Some("js"));
+    assert_eq!(options.mark_lines.as_deref(), Some("{2}"));
+    assert_eq!(options.caption.as_deref(), Some("<span>Example</span>"));
+
+    assert_eq!(parse_info("js {a}").mark_lines, None);
+  }
+
+  #[test]
+  fn test_parse_info_empty() {
+    let options = parse_info("");
+    assert_eq!(options.lang, None);
+    assert_eq!(options.caption, None);
+  }
+
+  #[test]
+  fn test_indented_code_block() {
+    let mut md = MarkdownIt::new();
+    markdown_it::plugins::cmark::add(&mut md);
+    add(&mut md);
+
+    let output = md.parse("    indented").render();
+    assert!(output.starts_with("<figure class=\"highlight plaintext\">"));
+    assert!(output.contains("<span class=\"line\">indented</span>"));
+  }
+
+  #[test]
+  fn test_class_prefix() {
+    let mut md = MarkdownIt::new();
+    markdown_it::plugins::cmark::add(&mut md);
+    add(&mut md);
+    set_settings(
+      &mut md,
+      HighlightSettings {
+        class_prefix: Some("hl-".to_string()),
+        ..Default::default()
+      },
+    );
+
+    let output = md.parse("```rust\nfn main() {}\n```").render();
+    assert!(output.contains("<span class=\"hl-source hl-rust\">"));
+  }
+
+  #[test]
+  fn test_settings() {
+    let mut md = MarkdownIt::new();
+    markdown_it::plugins::cmark::add(&mut md);
+    add(&mut md);
+    set_settings(
+      &mut md,
+      HighlightSettings {
+        layout: HighlightLayout::Pre,
+        gutter: false,
+        copy_button: true,
+        ..Default::default()
+      },
+    );
+
+    let output = md.parse("```plaintext =3\na\n```").render();
+    assert_eq!(
+      output,
+      "<figure class=\"highlight plaintext\"><div class=\"highlight-header\">\
+       <button type=\"button\" class=\"copy-button\" aria-label=\"Copy\"></button></div>\
+       <pre class=\"code\"><code data-line-start=\"3\"><span class=\"line\" data-line=\"3\">a</span></code></pre></figure>\n"
+    );
+  }
+
+  #[test]
+  fn test_info_string_with_quote() {
+    let mut md = MarkdownIt::new();
+    markdown_it::plugins::cmark::add(&mut md);
+    add(&mut md);
+
+    let output = md
+      .parse("```x\"><img/src=x/onerror=alert(1)>\na\n```")
+      .render();
+    assert!(output.starts_with("<figure class=\"highlight x&quot;&gt;&lt;img/src\"><table>"));
+
+    let output = md
+      .parse("```x\"><script>alert(1)</script>\na\n```")
+      .render();
+    assert!(output.starts_with(
+      "<figure class=\"highlight x&quot;&gt;&lt;script&gt;alert(1)&lt;/script&gt;\"><table>"
+    ));
+    assert!(!output.contains("<script>"));
+  }
+}
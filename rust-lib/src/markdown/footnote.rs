@@ -0,0 +1,352 @@
+use markdown_it::plugins::cmark::block::paragraph::Paragraph;
+use markdown_it::{Node, NodeValue, Renderer};
+use markdown_it_footnote::back_refs::FootnoteRefAnchor;
+use markdown_it_footnote::collect::FootnotesContainerNode;
+use markdown_it_footnote::definitions::FootnoteDefinition;
+use markdown_it_footnote::references::FootnoteReference;
+
+/// Rendering of footnotes, applied to each document after parsing.
+/// The default renders the same output as `markdown_it_footnote`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct FootnoteOptions {
+  /// Prepended to footnote ids (`fn1`, `fnref1`), e.g. derived from the content path so that
+  /// footnotes of several documents on one page don't collide.
+  pub id_prefix: Option<String>,
+  /// Text of the links back to the references. Default: `↩︎`
+  pub back_ref_symbol: Option<String>,
+  /// Heading rendered at the top of the footnote section.
+  pub heading: Option<String>,
+  /// `aria-label` of the footnote section.
+  pub label: Option<String>,
+  /// Render footnotes as `<aside>` after the block that references them, instead of a section at the end.
+  pub sidenotes: bool,
+}
+
+const DEFAULT_BACK_REF_SYMBOL: &str = "\u{21a9}\u{FE0E}";
+
+#[derive(Debug)]
+pub struct FootnoteRef {
+  pub label: Option<String>,
+  pub ref_id: usize,
+  pub def_id: usize,
+  pub id_prefix: String,
+}
+
+impl NodeValue for FootnoteRef {
+  fn render(&self, node: &Node, fmt: &mut dyn Renderer) {
+    let mut attrs = node.attrs.clone();
+    attrs.push(("class", "footnote-ref".into()));
+    fmt.open("sup", &attrs);
+    fmt.open(
+      "a",
+      &[
+        ("href", format!("#{}fn{}", self.id_prefix, self.def_id)),
+        ("id", format!("{}fnref{}", self.id_prefix, self.ref_id)),
+      ],
+    );
+    fmt.text(&format!("[{}]", self.def_id));
+    fmt.close("a");
+    fmt.close("sup");
+  }
+}
+
+#[derive(Debug)]
+pub struct FootnoteItem {
+  pub label: Option<String>,
+  pub def_id: Option<usize>,
+  pub id_prefix: String,
+  pub sidenote: bool,
+}
+
+impl NodeValue for FootnoteItem {
+  fn render(&self, node: &Node, fmt: &mut dyn Renderer) {
+    let mut attrs = node.attrs.clone();
+    if let Some(def_id) = self.def_id {
+      attrs.push(("id", format!("{}fn{}", self.id_prefix, def_id)));
+    }
+    let tag = if self.sidenote {
+      attrs.push(("class", "footnote-sidenote".into()));
+      "aside"
+    } else {
+      attrs.push(("class", "footnote-item".into()));
+      "li"
+    };
+
+    fmt.cr();
+    fmt.open(tag, &attrs);
+    fmt.contents(&node.children);
+    fmt.close(tag);
+    fmt.cr();
+  }
+}
+
+/// Number of a sidenote, matching the number of its reference.
+#[derive(Debug)]
+pub struct SidenoteNumber {
+  pub def_id: usize,
+}
+
+impl NodeValue for SidenoteNumber {
+  fn render(&self, _: &Node, fmt: &mut dyn Renderer) {
+    fmt.open("span", &[("class", "footnote-sidenote-number".into())]);
+    fmt.text(&self.def_id.to_string());
+    fmt.close("span");
+    fmt.text(" ");
+  }
+}
+
+#[derive(Debug)]
+pub struct FootnoteBackRefs {
+  pub ref_ids: Vec<usize>,
+  pub id_prefix: String,
+  pub symbol: String,
+}
+
+impl NodeValue for FootnoteBackRefs {
+  fn render(&self, _: &Node, fmt: &mut dyn Renderer) {
+    for ref_id in self.ref_ids.iter() {
+      fmt.text(" ");
+      fmt.open(
+        "a",
+        &[
+          ("href", format!("#{}fnref{}", self.id_prefix, ref_id)),
+          ("class", "footnote-backref".into()),
+        ],
+      );
+      fmt.text(&self.symbol);
+      fmt.close("a");
+    }
+  }
+}
+
+#[derive(Debug)]
+pub struct FootnoteSection {
+  pub heading: Option<String>,
+  pub label: Option<String>,
+}
+
+impl NodeValue for FootnoteSection {
+  fn render(&self, node: &Node, fmt: &mut dyn Renderer) {
+    let mut attrs = node.attrs.clone();
+    attrs.push(("class", "footnotes".into()));
+    if let Some(label) = &self.label {
+      attrs.push(("aria-label", label.clone()));
+    }
+    fmt.cr();
+    fmt.self_close("hr", &[("class", "footnotes-sep".into())]);
+    fmt.cr();
+    fmt.open("section", &attrs);
+    fmt.cr();
+    if let Some(heading) = &self.heading {
+      fmt.open("h2", &[("class", "footnotes-title".into())]);
+      fmt.text(heading);
+      fmt.close("h2");
+      fmt.cr();
+    }
+    fmt.open("ol", &[("class", "footnotes-list".into())]);
+    fmt.cr();
+    fmt.contents(&node.children);
+    fmt.cr();
+    fmt.close("ol");
+    fmt.cr();
+    fmt.close("section");
+    fmt.cr();
+  }
+}
+
+/// Applies `options` to the footnotes collected by `markdown_it_footnote`.
+///
+/// # Examples
+///
+/// ```
+/// // id_prefix: example-, sidenotes: true
+///
+/// // Input:
+/// // Text[^1].
+/// //
+/// // [^1]: Note
+///
+/// // Rendered output:
+/// // <p>Text<sup class="footnote-ref"><a href="#example-fn1" id="example-fnref1">[1]</a></sup>.</p>
+/// // <aside id="example-fn1" class="footnote-sidenote">
+/// // <p><span class="footnote-sidenote-number">1</span> Note</p>
+/// // </aside>
+/// ```
+pub fn process(root: &mut Node, options: &FootnoteOptions) {
+  if *options == FootnoteOptions::default() {
+    return;
+  }
+  let id_prefix = options.id_prefix.clone().unwrap_or_default();
+  let symbol = options
+    .back_ref_symbol
+    .clone()
+    .unwrap_or_else(|| DEFAULT_BACK_REF_SYMBOL.to_string());
+
+  root.walk_mut(|node, _| {
+    if let Some(reference) = node.cast::<FootnoteReference>() {
+      let value = FootnoteRef {
+        label: reference.label.clone(),
+        ref_id: reference.ref_id,
+        def_id: reference.def_id,
+        id_prefix: id_prefix.clone(),
+      };
+      node.replace(value);
+    } else if let Some(definition) = node.cast::<FootnoteDefinition>() {
+      let value = FootnoteItem {
+        label: definition.label.clone(),
+        def_id: definition.def_id,
+        id_prefix: id_prefix.clone(),
+        sidenote: options.sidenotes,
+      };
+      node.replace(value);
+    } else if let Some(anchor) = node.cast::<FootnoteRefAnchor>() {
+      let value = FootnoteBackRefs {
+        ref_ids: anchor.ref_ids.clone(),
+        id_prefix: id_prefix.clone(),
+        symbol: symbol.clone(),
+      };
+      node.replace(value);
+    } else if node.is::<FootnotesContainerNode>() {
+      node.replace(FootnoteSection {
+        heading: options.heading.clone(),
+        label: options.label.clone(),
+      });
+    }
+  });
+
+  if options.sidenotes {
+    place_sidenotes(root);
+  }
+}
+
+/// Moves the footnotes from the section to after the top-level block of their first reference.
+fn place_sidenotes(root: &mut Node) {
+  let Some(section) = root
+    .children
+    .iter()
+    .position(|node| node.is::<FootnoteSection>())
+  else {
+    return;
+  };
+  let mut section = root.children.remove(section);
+  let mut notes: Vec<Option<Node>> = std::mem::take(&mut section.children)
+    .into_iter()
+    .map(Some)
+    .collect();
+
+  let blocks = std::mem::take(&mut root.children);
+  for block in blocks {
+    let mut def_ids = vec![];
+    block.walk(|node, _| {
+      if let Some(reference) = node.cast::<FootnoteRef>() {
+        def_ids.push(reference.def_id);
+      }
+    });
+    root.children.push(block);
+
+    for def_id in def_ids {
+      let found = notes.iter_mut().find(|note| {
+        note
+          .as_ref()
+          .and_then(|n| n.cast::<FootnoteItem>())
+          .is_some_and(|item| item.def_id == Some(def_id))
+      });
+      if let Some(mut note) = found.and_then(Option::take) {
+        // back references are next to the sidenote
+        note.walk_mut(|node, _| {
+          node
+            .children
+            .retain(|child| !child.is::<FootnoteBackRefs>())
+        });
+        add_number(&mut note, def_id);
+        root.children.push(note);
+      }
+    }
+  }
+}
+
+fn add_number(note: &mut Node, def_id: usize) {
+  let number = Node::new(SidenoteNumber { def_id });
+  match note.children.first_mut() {
+    Some(paragraph) if paragraph.is::<Paragraph>() => paragraph.children.insert(0, number),
+    _ => note.children.insert(0, number),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use markdown_it::MarkdownIt;
+
+  fn render(input: &str, options: &FootnoteOptions) -> String {
+    let mut md = MarkdownIt::new();
+    markdown_it::plugins::cmark::add(&mut md);
+    markdown_it_footnote::add(&mut md);
+    let mut ast = md.parse(input);
+    process(&mut ast, options);
+    ast.render()
+  }
+
+  const INPUT: &str = "A[^a] and B^[inline].\n\nAgain[^a].\n\n[^a]: Note";
+
+  #[test]
+  fn test_default_output_is_unchanged() {
+    let mut md = MarkdownIt::new();
+    markdown_it::plugins::cmark::add(&mut md);
+    markdown_it_footnote::add(&mut md);
+    let expected = md.parse(INPUT).render();
+
+    let options = FootnoteOptions {
+      back_ref_symbol: Some(DEFAULT_BACK_REF_SYMBOL.to_string()),
+      ..Default::default()
+    };
+    assert_eq!(render(INPUT, &options), expected);
+  }
+
+  #[test]
+  fn test_section_options() {
+    let options = FootnoteOptions {
+      id_prefix: Some("post-".to_string()),
+      back_ref_symbol: Some("^".to_string()),
+      heading: Some("Notes".to_string()),
+      label: Some("Footnotes".to_string()),
+      sidenotes: false,
+    };
+    assert_eq!(
+      render(INPUT, &options),
+      "<p>A<sup class=\"footnote-ref\"><a href=\"#post-fn1\" id=\"post-fnref1\">[1]</a></sup> and B<sup class=\"footnote-ref\"><a href=\"#post-fn2\" id=\"post-fnref2\">[2]</a></sup>.</p>\n\
+       <p>Again<sup class=\"footnote-ref\"><a href=\"#post-fn1\" id=\"post-fnref3\">[1]</a></sup>.</p>\n\
+       <hr class=\"footnotes-sep\">\n\
+       <section class=\"footnotes\" aria-label=\"Footnotes\">\n\
+       <h2 class=\"footnotes-title\">Notes</h2>\n\
+       <ol class=\"footnotes-list\">\n\
+       <li id=\"post-fn1\" class=\"footnote-item\">\n\
+       <p>Note <a href=\"#post-fnref1\" class=\"footnote-backref\">^</a> <a href=\"#post-fnref3\" class=\"footnote-backref\">^</a></p>\n\
+       </li>\n\
+       <li id=\"post-fn2\" class=\"footnote-item\">\n\
+       <p>inline <a href=\"#post-fnref2\" class=\"footnote-backref\">^</a></p>\n\
+       </li>\n\
+       </ol>\n\
+       </section>\n"
+    );
+  }
+
+  #[test]
+  fn test_sidenotes() {
+    let options = FootnoteOptions {
+      sidenotes: true,
+      ..Default::default()
+    };
+    assert_eq!(
+      render(INPUT, &options),
+      "<p>A<sup class=\"footnote-ref\"><a href=\"#fn1\" id=\"fnref1\">[1]</a></sup> and B<sup class=\"footnote-ref\"><a href=\"#fn2\" id=\"fnref2\">[2]</a></sup>.</p>\n\
+       <aside id=\"fn1\" class=\"footnote-sidenote\">\n\
+       <p><span class=\"footnote-sidenote-number\">1</span> Note</p>\n\
+       </aside>\n\
+       <aside id=\"fn2\" class=\"footnote-sidenote\">\n\
+       <p><span class=\"footnote-sidenote-number\">2</span> inline</p>\n\
+       </aside>\n\
+       <p>Again<sup class=\"footnote-ref\"><a href=\"#fn1\" id=\"fnref3\">[1]</a></sup>.</p>\n"
+    );
+  }
+}
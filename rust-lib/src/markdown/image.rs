@@ -0,0 +1,412 @@
+use std::path::{Component, Path, PathBuf};
+
+use markdown_it::plugins::cmark::block::paragraph::Paragraph;
+use markdown_it::plugins::cmark::inline::image::Image;
+use markdown_it::{Node, NodeValue, Renderer};
+use markdown_it_lazyload::LazyLoadImage;
+use percent_encoding::percent_decode_str;
+
+use crate::image_validator;
+
+/// Post-processing of images, applied to each document after parsing.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ImageOptions {
+  /// Directory relative image paths are resolved against, e.g. the post asset folder.
+  pub asset_dir: Option<PathBuf>,
+  /// Prepended to relative image urls, e.g. `/articles/2024/02/example/`.
+  pub url_prefix: Option<String>,
+  /// Emit `width` and `height` read from the image files in `asset_dir`.
+  pub dimensions: bool,
+  /// Widths of pre-generated variants named `{stem}-{width}w.{ext}` next to the image.
+  pub srcset_widths: Vec<u32>,
+  /// Wrap images with a title that are alone in a paragraph into `<figure>`.
+  pub figures: bool,
+}
+
+impl Default for ImageOptions {
+  fn default() -> Self {
+    Self {
+      asset_dir: None,
+      url_prefix: None,
+      dimensions: true,
+      srcset_widths: vec![],
+      figures: true,
+    }
+  }
+}
+
+#[derive(Debug)]
+pub struct ProcessedImage {
+  pub url: String,
+  pub title: Option<String>,
+  pub width: Option<u32>,
+  pub height: Option<u32>,
+  pub srcset: Option<String>,
+  pub lazy: bool,
+}
+
+impl NodeValue for ProcessedImage {
+  fn render(&self, node: &Node, fmt: &mut dyn Renderer) {
+    let mut attrs = node.attrs.clone();
+    attrs.push(("src", self.url.clone()));
+    attrs.push(("alt", node.collect_text()));
+    if let Some(title) = &self.title {
+      attrs.push(("title", title.clone()));
+    }
+    if let (Some(width), Some(height)) = (self.width, self.height) {
+      attrs.push(("width", width.to_string()));
+      attrs.push(("height", height.to_string()));
+    }
+    if let Some(srcset) = &self.srcset {
+      attrs.push(("srcset", srcset.clone()));
+    }
+    if self.lazy {
+      attrs.push(("loading", "lazy".to_string()));
+    }
+    fmt.self_close("img", &attrs);
+  }
+}
+
+#[derive(Debug)]
+pub struct Figure {
+  pub caption: String,
+}
+
+impl NodeValue for Figure {
+  fn render(&self, node: &Node, fmt: &mut dyn Renderer) {
+    fmt.cr();
+    fmt.open("figure", &node.attrs);
+    fmt.contents(&node.children);
+    fmt.open("figcaption", &[]);
+    fmt.text(&self.caption);
+    fmt.close("figcaption");
+    fmt.close("figure");
+    fmt.cr();
+  }
+}
+
+/// Resolves local images, adds their dimensions and `srcset` and wraps titled images into figures.
+///
+/// # Examples
+///
+/// ```
+/// // asset_dir: source/_posts/example, url_prefix: /articles/2024/02/example/
+///
+/// // Input:
+/// // ![Diagram](diagram.png "Overview")
+///
+/// // Rendered output:
+/// // <figure><img src="/articles/2024/02/example/diagram.png" alt="Diagram" width="800" height="600" loading="lazy">
+/// // <figcaption>Overview</figcaption></figure>
+/// ```
+pub fn process(root: &mut Node, options: &ImageOptions) {
+  root.walk_mut(|node, _| {
+    let (image, lazy) = if let Some(image) = node.cast::<Image>() {
+      (image, false)
+    } else if let Some(image) = node.cast::<LazyLoadImage>() {
+      (&image.cmark_image, !image.cmark_image.url.trim().is_empty())
+    } else {
+      return;
+    };
+
+    let mut processed = ProcessedImage {
+      url: image.url.clone(),
+      title: image.title.clone(),
+      width: None,
+      height: None,
+      srcset: None,
+      lazy,
+    };
+    if is_local(&image.url) {
+      resolve(&mut processed, options);
+    }
+    node.replace(processed);
+  });
+
+  if options.figures {
+    wrap_figures(root);
+  }
+}
+
+/// Relative urls without a scheme, e.g. `image.png` or `images/a.png`.
+fn is_local(url: &str) -> bool {
+  !url.is_empty()
+    && !url.starts_with(['/', '#', '?'])
+    && !url
+      .split(['/', '?', '#'])
+      .next()
+      .unwrap_or_default()
+      .contains(':')
+}
+
+fn resolve(image: &mut ProcessedImage, options: &ImageOptions) {
+  let relative = image.url.clone();
+
+  if let Some(dir) = &options.asset_dir
+    && let Some(path) = asset_path(dir, &relative)
+  {
+    if options.dimensions
+      && let Some((width, height)) = displayed_size(&path)
+    {
+      image.width = Some(width);
+      image.height = Some(height);
+    }
+    image.srcset = srcset(&path, &relative, options);
+  }
+
+  if let Some(prefix) = &options.url_prefix {
+    image.url = format!("{}{}", prefix, relative);
+    if let Some(srcset) = &image.srcset {
+      image.srcset = Some(
+        srcset
+          .split(", ")
+          .map(|candidate| format!("{}{}", prefix, candidate))
+          .collect::<Vec<_>>()
+          .join(", "),
+      );
+    }
+  }
+}
+
+/// The file of a local image url in `dir`. `None` when the decoded path is absolute or has `..`
+/// components, so that urls such as `..%2F..%2Fsecret.png` don't read files outside of `dir`.
+fn asset_path(dir: &Path, url: &str) -> Option<PathBuf> {
+  let path = url.split(['?', '#']).next().unwrap_or_default();
+  let decoded = percent_decode_str(path).decode_utf8_lossy();
+  let path = Path::new(decoded.as_ref());
+  path
+    .components()
+    .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+    .then(|| dir.join(path))
+}
+
+/// Width and height of the image as displayed, swapped for EXIF orientations 5 to 8, which rotate
+/// it by 90 degrees.
+fn displayed_size(path: &Path) -> Option<(u32, u32)> {
+  let size = imagesize::size(path).ok()?;
+  let (width, height) = (size.width as u32, size.height as u32);
+  match image_validator::orientation(path) {
+    Some(5..=8) => Some((height, width)),
+    _ => Some((width, height)),
+  }
+}
+
+/// Builds `srcset` from the variants that exist on disk, together with the original image.
+fn srcset(path: &Path, url: &str, options: &ImageOptions) -> Option<String> {
+  let stem = path.file_stem()?.to_str()?;
+  let extension = path.extension()?.to_str()?;
+  let url_dir = url.rfind('/').map_or("", |i| &url[..=i]);
+  let url_stem = url[url_dir.len()..].rsplit_once('.')?.0;
+
+  let mut candidates: Vec<(u32, String)> = options
+    .srcset_widths
+    .iter()
+    .filter(|width| {
+      path
+        .with_file_name(format!("{}-{}w.{}", stem, width, extension))
+        .is_file()
+    })
+    .map(|width| {
+      let variant = format!("{}{}-{}w.{}", url_dir, url_stem, width, extension);
+      (*width, variant)
+    })
+    .collect();
+  if candidates.is_empty() {
+    return None;
+  }
+
+  if let Some((width, _)) = displayed_size(path) {
+    candidates.push((width, url.to_string()));
+  }
+  candidates.sort_by_key(|(width, _)| *width);
+  candidates.dedup_by_key(|(width, _)| *width);
+
+  Some(
+    candidates
+      .iter()
+      .map(|(width, url)| format!("{} {}w", url, width))
+      .collect::<Vec<_>>()
+      .join(", "),
+  )
+}
+
+/// Replaces paragraphs that only contain a titled image with a figure.
+fn wrap_figures(root: &mut Node) {
+  root.walk_mut(|node, _| {
+    if !node.is::<Paragraph>() || node.children.len() != 1 {
+      return;
+    }
+    let Some(image) = node.children[0].cast_mut::<ProcessedImage>() else {
+      return;
+    };
+    let Some(caption) = image.title.take() else {
+      return;
+    };
+    node.replace(Figure { caption });
+  });
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use markdown_it::MarkdownIt;
+  use std::fs;
+
+  fn render(input: &str, options: &ImageOptions) -> String {
+    let mut md = MarkdownIt::new();
+    markdown_it::plugins::cmark::add(&mut md);
+    let mut ast = md.parse(input);
+    process(&mut ast, options);
+    ast.render()
+  }
+
+  /// Writes the header of a PNG, which is enough to read its dimensions.
+  fn write_png(path: &Path, width: u32, height: u32) {
+    let mut bytes = b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR".to_vec();
+    bytes.extend(width.to_be_bytes());
+    bytes.extend(height.to_be_bytes());
+    bytes.extend([8, 6, 0, 0, 0, 0, 0, 0, 0]);
+    fs::write(path, bytes).unwrap();
+  }
+
+  /// Writes a JPEG with an EXIF orientation and a frame header, without image data.
+  fn write_jpeg(path: &Path, width: u16, height: u16, orientation: u16) {
+    let mut bytes =
+      b"\xff\xd8\xff\xe1\0\x22Exif\0\0MM\0\x2a\0\0\0\x08\0\x01\x01\x12\0\x03\0\0\0\x01".to_vec();
+    bytes.extend(orientation.to_be_bytes());
+    bytes.extend(b"\0\0\0\0\0\0\xff\xc0\0\x11\x08");
+    bytes.extend(height.to_be_bytes());
+    bytes.extend(width.to_be_bytes());
+    bytes.extend(b"\x03\x01\x22\0\x02\x11\x01\x03\x11\x01\xff\xd9");
+    fs::write(path, bytes).unwrap();
+  }
+
+  fn asset_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("qualtet-cli-image-{}", name));
+    fs::create_dir_all(&dir).unwrap();
+    dir
+  }
+
+  #[test]
+  fn test_dimensions_and_url_prefix() {
+    let dir = asset_dir("dimensions");
+    write_png(&dir.join("a b.png"), 800, 600);
+
+    let options = ImageOptions {
+      asset_dir: Some(dir),
+      url_prefix: Some("/articles/2024/02/example/".to_string()),
+      ..Default::default()
+    };
+    let output = render(
+      "![A](a%20b.png) ![B](missing.png) ![C](https://example.com/c.png)",
+      &options,
+    );
+    assert_eq!(
+      output,
+      "<p><img src=\"/articles/2024/02/example/a%20b.png\" alt=\"A\" width=\"800\" height=\"600\"> \
+       <img src=\"/articles/2024/02/example/missing.png\" alt=\"B\"> \
+       <img src=\"https://example.com/c.png\" alt=\"C\"></p>\n"
+    );
+  }
+
+  #[test]
+  fn test_exif_orientation() {
+    let dir = asset_dir("orientation");
+    write_jpeg(&dir.join("rotated.jpg"), 800, 600, 6);
+    write_jpeg(&dir.join("upright.jpg"), 800, 600, 1);
+    write_jpeg(&dir.join("rotated-400w.jpg"), 400, 300, 6);
+
+    let options = ImageOptions {
+      asset_dir: Some(dir),
+      srcset_widths: vec![400],
+      ..Default::default()
+    };
+    assert_eq!(
+      render("![R](rotated.jpg) ![U](upright.jpg)", &options),
+      "<p><img src=\"rotated.jpg\" alt=\"R\" width=\"600\" height=\"800\" srcset=\"rotated-400w.jpg 400w, rotated.jpg 600w\"> \
+       <img src=\"upright.jpg\" alt=\"U\" width=\"800\" height=\"600\"></p>\n"
+    );
+  }
+
+  #[test]
+  fn test_paths_outside_asset_dir() {
+    let dir = asset_dir("outside");
+    write_png(&dir.join("secret.png"), 800, 600);
+    let posts = dir.join("post");
+    fs::create_dir_all(&posts).unwrap();
+    let absolute = dir.join("secret.png").to_string_lossy().replace('/', "%2F");
+
+    let options = ImageOptions {
+      asset_dir: Some(posts),
+      ..Default::default()
+    };
+    let output = render(
+      &format!(
+        "![A](../secret.png) ![B](..%2Fsecret.png) ![C]({})",
+        absolute
+      ),
+      &options,
+    );
+    assert!(!output.contains("width"), "{}", output);
+
+    assert_eq!(
+      asset_path(&dir, "a/./b.png?v=1"),
+      Some(dir.join("a/./b.png"))
+    );
+    assert_eq!(asset_path(&dir, "a/../../b.png"), None);
+  }
+
+  #[test]
+  fn test_srcset() {
+    let dir = asset_dir("srcset");
+    write_png(&dir.join("photo.png"), 1600, 1200);
+    write_png(&dir.join("photo-400w.png"), 400, 300);
+    write_png(&dir.join("photo-800w.png"), 800, 600);
+
+    let options = ImageOptions {
+      asset_dir: Some(dir),
+      srcset_widths: vec![400, 800, 1200],
+      dimensions: false,
+      ..Default::default()
+    };
+    let output = render("![P](photo.png)", &options);
+    assert_eq!(
+      output,
+      "<p><img src=\"photo.png\" alt=\"P\" srcset=\"photo-400w.png 400w, photo-800w.png 800w, photo.png 1600w\"></p>\n"
+    );
+  }
+
+  #[test]
+  fn test_figure() {
+    let options = ImageOptions::default();
+    let output = render(
+      "![A](a.png \"Caption <1>\")\n\ntext ![B](b.png \"Inline\")",
+      &options,
+    );
+    assert_eq!(
+      output,
+      "<figure><img src=\"a.png\" alt=\"A\"><figcaption>Caption &lt;1&gt;</figcaption></figure>\n\
+       <p>text <img src=\"b.png\" alt=\"B\" title=\"Inline\"></p>\n"
+    );
+
+    let options = ImageOptions {
+      figures: false,
+      ..Default::default()
+    };
+    let output = render("![A](a.png \"Caption\")", &options);
+    assert_eq!(
+      output,
+      "<p><img src=\"a.png\" alt=\"A\" title=\"Caption\"></p>\n"
+    );
+  }
+
+  #[test]
+  fn test_is_local() {
+    assert!(is_local("image.png"));
+    assert!(is_local("images/a:b.png"));
+    assert!(!is_local("/images/a.png"));
+    assert!(!is_local("//example.com/a.png"));
+    assert!(!is_local("data:image/png;base64,AAAA"));
+    assert!(!is_local(""));
+  }
+}
@@ -0,0 +1,229 @@
+use std::collections::HashSet;
+use std::sync::LazyLock;
+
+use markdown_it::Node;
+use markdown_it::common::sourcemap::SourceWithLineStarts;
+use markdown_it::parser::core::Root;
+use markdown_it::parser::inline::Text;
+use markdown_it::plugins::cmark::inline::link::Link;
+use markdown_it::plugins::html::html_block::HtmlBlock;
+use markdown_it::plugins::html::html_inline::HtmlInline;
+use percent_encoding::percent_decode_str;
+use regex::Regex;
+use url::Url;
+
+use crate::utils::format_path;
+
+static HTML_ID: LazyLock<Regex> =
+  LazyLock::new(|| Regex::new(r#"\sid\s*=\s*["']([^"']+)["']"#).unwrap());
+static FOOTNOTE_REFERENCE: LazyLock<Regex> =
+  LazyLock::new(|| Regex::new(r"\[\^([^\]\s]+)\]").unwrap());
+
+/// Only used to resolve relative paths, never requested.
+const BASE_URL: &str = "https://example.invalid";
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LinkCheckOptions {
+  /// Formatted path of the content being rendered (e.g. `/articles/2024/01/example/`).
+  pub content_path: String,
+  /// Paths of every published content, normalized with `format_path` before comparing.
+  pub known_paths: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BrokenLinkKind {
+  /// `#fragment` without a heading (or HTML element) with that id.
+  Fragment,
+  /// Relative link to a path that isn't a known content path.
+  Path,
+  /// `[^label]` without a footnote definition.
+  Footnote,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LinkDiagnostic {
+  pub line: usize,
+  pub kind: BrokenLinkKind,
+  pub target: String,
+  pub message: String,
+}
+
+/// Validates in-document fragments, relative links and footnote references.
+///
+/// Links to files other than HTML (e.g. `./slides.pdf`) are assets and aren't validated.
+/// Fragments of links to other documents aren't validated either.
+pub fn check(root: &Node, options: &LinkCheckOptions, footnotes: bool) -> Vec<LinkDiagnostic> {
+  let Some(source) = root.cast::<Root>().map(|r| &r.content) else {
+    return Vec::new();
+  };
+  let map = SourceWithLineStarts::new(source);
+
+  let ids = collect_ids(root);
+  let known_paths: HashSet<String> = options
+    .known_paths
+    .iter()
+    .map(|path| format_path(path, ""))
+    .collect();
+  let base = Url::parse(BASE_URL).and_then(|url| url.join(&options.content_path));
+
+  let mut diagnostics = Vec::new();
+  walk(root, 0, &map, &mut |node, line| {
+    if let Some(link) = node.cast::<Link>() {
+      if let Some(fragment) = link.url.strip_prefix('#') {
+        let id = percent_decode_str(fragment).decode_utf8_lossy();
+        if !fragment.is_empty() && !ids.contains(id.as_ref()) {
+          diagnostics.push(LinkDiagnostic {
+            line,
+            kind: BrokenLinkKind::Fragment,
+            target: link.url.clone(),
+            message: format!("no heading with id `{}`", id),
+          });
+        }
+      } else if let Some(path) = resolve(&link.url, base.as_ref().ok())
+        && !known_paths.contains(&path)
+      {
+        diagnostics.push(LinkDiagnostic {
+          line,
+          kind: BrokenLinkKind::Path,
+          target: link.url.clone(),
+          message: format!("`{}` is not a known content path", path),
+        });
+      }
+    } else if footnotes && let Some(text) = node.cast::<Text>() {
+      // references to undefined footnotes are left as text
+      for caps in FOOTNOTE_REFERENCE.captures_iter(&text.content) {
+        diagnostics.push(LinkDiagnostic {
+          line,
+          kind: BrokenLinkKind::Footnote,
+          target: caps[0].to_string(),
+          message: format!("footnote `{}` is not defined", &caps[1]),
+        });
+      }
+    }
+  });
+  diagnostics
+}
+
+/// Visits nodes with the line of the closest node that has a source map.
+fn walk(node: &Node, line: usize, map: &SourceWithLineStarts, f: &mut impl FnMut(&Node, usize)) {
+  let line = node
+    .srcmap
+    .map(|pos| pos.get_positions(map).0.0 as usize)
+    .unwrap_or(line);
+  f(node, line);
+  for child in node.children.iter() {
+    walk(child, line, map, f);
+  }
+}
+
+/// Heading ids, and ids of raw HTML elements.
+fn collect_ids(root: &Node) -> HashSet<String> {
+  let mut ids = HashSet::new();
+  root.walk(|node, _| {
+    ids.extend(
+      node
+        .attrs
+        .iter()
+        .filter(|(name, _)| *name == "id")
+        .map(|(_, value)| value.clone()),
+    );
+
+    let html = if let Some(html) = node.cast::<HtmlBlock>() {
+      &html.content
+    } else if let Some(html) = node.cast::<HtmlInline>() {
+      &html.content
+    } else {
+      return;
+    };
+    ids.extend(HTML_ID.captures_iter(html).map(|caps| caps[1].to_string()));
+  });
+  ids
+}
+
+/// Resolves a relative link against the content path, or returns `None` when it isn't a content link.
+fn resolve(url: &str, base: Option<&Url>) -> Option<String> {
+  if url.is_empty() || url.starts_with("//") || Url::parse(url).is_ok() {
+    return None;
+  }
+  let resolved = base?.join(url).ok()?;
+  let path = percent_decode_str(resolved.path())
+    .decode_utf8_lossy()
+    .to_string();
+
+  let last_segment = path.rsplit('/').next().unwrap_or_default();
+  if let Some((_, extension)) = last_segment.rsplit_once('.')
+    && extension != "html"
+  {
+    return None;
+  }
+  Some(format_path(&path, ""))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use markdown_it::MarkdownIt;
+
+  fn check_links(input: &str, known_paths: &[&str]) -> Vec<LinkDiagnostic> {
+    let mut md = MarkdownIt::new();
+    markdown_it::plugins::cmark::add(&mut md);
+    markdown_it::plugins::html::add(&mut md);
+    super::super::anchor::add(&mut md);
+    let ast = md.parse(input);
+    let options = LinkCheckOptions {
+      content_path: "/articles/2024/01/example/".to_string(),
+      known_paths: known_paths.iter().map(|p| p.to_string()).collect(),
+    };
+    check(&ast, &options, true)
+  }
+
+  #[test]
+  fn test_fragments() {
+    let input = "# Getting started\n\n## 日本語\n\n<div id=\"raw\"></div>\n\n[a](#getting-started) [b](#日本語) [c](#raw)\n\n[d](#missing)";
+    let diagnostics = check_links(input, &[]);
+    assert_eq!(
+      diagnostics,
+      vec![LinkDiagnostic {
+        line: 9,
+        kind: BrokenLinkKind::Fragment,
+        target: "#missing".to_string(),
+        message: "no heading with id `missing`".to_string(),
+      }]
+    );
+  }
+
+  #[test]
+  fn test_paths() {
+    let known = ["/articles/2024/01/other/", "/about"];
+    let input = "[a](../other/) [b](/about/) [c](/articles/2024/01/other/index.html#x)\n\
+                 [d](https://example.com/x) [e](mailto:a@example.com) [f](slides.pdf)\n\
+                 \n\
+                 [g](../missing/)";
+    let diagnostics = check_links(input, &known);
+    assert_eq!(
+      diagnostics,
+      vec![LinkDiagnostic {
+        line: 4,
+        kind: BrokenLinkKind::Path,
+        target: "../missing/".to_string(),
+        message: "`/articles/2024/01/missing/` is not a known content path".to_string(),
+      }]
+    );
+  }
+
+  #[test]
+  fn test_undefined_footnote() {
+    let mut md = MarkdownIt::new();
+    markdown_it::plugins::cmark::add(&mut md);
+    markdown_it_footnote::add(&mut md);
+    let ast = md.parse("Defined[^1] and undefined[^2].\n\n[^1]: Note");
+
+    let diagnostics = check(&ast, &LinkCheckOptions::default(), true);
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].kind, BrokenLinkKind::Footnote);
+    assert_eq!(diagnostics[0].target, "[^2]");
+    assert_eq!(diagnostics[0].line, 1);
+
+    assert!(check(&ast, &LinkCheckOptions::default(), false).is_empty());
+  }
+}
@@ -0,0 +1,555 @@
+use std::collections::HashSet;
+use std::ops::Range;
+use std::sync::LazyLock;
+
+use markdown_it::Node;
+use markdown_it::parser::inline::Text;
+use markdown_it::plugins::cmark::block::heading::ATXHeading;
+use markdown_it::plugins::cmark::block::lheading::SetextHeader;
+use markdown_it::plugins::cmark::inline::autolink::Autolink;
+use markdown_it::plugins::cmark::inline::backticks::CodeInline;
+use markdown_it::plugins::cmark::inline::image::Image;
+use markdown_it::plugins::cmark::inline::link::Link;
+use markdown_it_lazyload::LazyLoadImage;
+use regex::Regex;
+
+use super::fence::HighlightedCode;
+use super::image::ProcessedImage;
+use crate::highlight;
+use crate::tag_plugin;
+
+static BARE_URL: LazyLock<Regex> =
+  LazyLock::new(|| Regex::new(r#"https?://[^\s<>"'`()\[\]]+"#).unwrap());
+static FOOTNOTE_DEFINITION: LazyLock<Regex> =
+  LazyLock::new(|| Regex::new(r"(?m)^ {0,3}\[\^([^\]\s]+)\]:").unwrap());
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+  /// The output is broken, e.g. the rest of the document is swallowed by an unclosed tag.
+  Error,
+  Warning,
+}
+
+/// Replaces `range` of the input with `text`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LintFix {
+  /// Byte range of the input.
+  pub range: Range<usize>,
+  pub line: usize,
+  pub column: usize,
+  pub end_line: usize,
+  pub end_column: usize,
+  pub text: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LintDiagnostic {
+  pub rule: &'static str,
+  pub severity: Severity,
+  /// 1-based line in the input, including the front matter.
+  pub line: usize,
+  /// 1-based column, counted in characters.
+  pub column: usize,
+  pub message: String,
+  pub fix: Option<LintFix>,
+}
+
+/// The markdown being linted. Offsets are relative to the markdown, after the front matter.
+pub struct Document<'a> {
+  pub source: &'a str,
+  pub ast: &'a Node,
+  pub footnotes: bool,
+  pub diagram_languages: Option<&'a [String]>,
+}
+
+/// A problem found by a rule, at a byte offset of the markdown.
+struct Finding {
+  offset: usize,
+  message: String,
+  fix: Option<(Range<usize>, String)>,
+}
+
+impl Finding {
+  fn new(offset: usize, message: String) -> Self {
+    Self {
+      offset,
+      message,
+      fix: None,
+    }
+  }
+
+  fn with_fix(mut self, range: Range<usize>, text: String) -> Self {
+    self.fix = Some((range, text));
+    self
+  }
+}
+
+struct Rule {
+  id: &'static str,
+  severity: Severity,
+  check: fn(&Document) -> Vec<Finding>,
+}
+
+static RULES: &[Rule] = &[
+  Rule {
+    id: "heading-increment",
+    severity: Severity::Warning,
+    check: heading_increment,
+  },
+  Rule {
+    id: "image-alt-text",
+    severity: Severity::Warning,
+    check: image_alt_text,
+  },
+  Rule {
+    id: "no-bare-urls",
+    severity: Severity::Warning,
+    check: no_bare_urls,
+  },
+  Rule {
+    id: "no-trailing-spaces",
+    severity: Severity::Warning,
+    check: no_trailing_spaces,
+  },
+  Rule {
+    id: "unclosed-hexo-tag",
+    severity: Severity::Error,
+    check: unclosed_hexo_tag,
+  },
+  Rule {
+    id: "unknown-fence-language",
+    severity: Severity::Warning,
+    check: unknown_fence_language,
+  },
+  Rule {
+    id: "duplicate-footnote-label",
+    severity: Severity::Error,
+    check: duplicate_footnote_label,
+  },
+];
+
+/// Returns the byte length of a `---` front matter at the start of `input`, or 0 without one.
+pub fn front_matter_len(input: &str) -> usize {
+  let Some(rest) = input
+    .strip_prefix("---\n")
+    .or_else(|| input.strip_prefix("---\r\n"))
+  else {
+    return 0;
+  };
+
+  let mut len = input.len() - rest.len();
+  for line in rest.split_inclusive('\n') {
+    len += line.len();
+    if line.trim_end() == "---" {
+      return len;
+    }
+  }
+  0
+}
+
+/// Runs every rule on `document`, which starts at byte `offset` of `input`.
+/// Diagnostics are sorted by their position.
+pub fn check(input: &str, offset: usize, document: &Document) -> Vec<LintDiagnostic> {
+  let mut diagnostics: Vec<LintDiagnostic> = RULES
+    .iter()
+    .flat_map(|rule| {
+      (rule.check)(document)
+        .into_iter()
+        .map(move |finding| (rule, finding))
+    })
+    .map(|(rule, finding)| {
+      let (line, column) = position(input, offset + finding.offset);
+      let fix = finding.fix.map(|(range, text)| {
+        let range = offset + range.start..offset + range.end;
+        let (line, column) = position(input, range.start);
+        let (end_line, end_column) = position(input, range.end);
+        LintFix {
+          range,
+          line,
+          column,
+          end_line,
+          end_column,
+          text,
+        }
+      });
+      LintDiagnostic {
+        rule: rule.id,
+        severity: rule.severity,
+        line,
+        column,
+        message: finding.message,
+        fix,
+      }
+    })
+    .collect();
+  diagnostics.sort_by_key(|d| (d.line, d.column));
+  diagnostics
+}
+
+/// Applies the fixes of `diagnostics` to `input`. Fixes overlapping a previous one are skipped.
+pub fn apply_fixes(input: &str, diagnostics: &[LintDiagnostic]) -> String {
+  let mut fixes: Vec<&LintFix> = diagnostics.iter().filter_map(|d| d.fix.as_ref()).collect();
+  fixes.sort_by_key(|fix| (fix.range.start, fix.range.end));
+
+  let mut result = String::with_capacity(input.len());
+  let mut pos = 0;
+  for fix in fixes {
+    if fix.range.start < pos {
+      continue;
+    }
+    result.push_str(&input[pos..fix.range.start]);
+    result.push_str(&fix.text);
+    pos = fix.range.end;
+  }
+  result.push_str(&input[pos..]);
+  result
+}
+
+/// 1-based line and column (in characters) of a byte offset.
+fn position(input: &str, offset: usize) -> (usize, usize) {
+  let before = &input[..offset];
+  let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+  (
+    before.matches('\n').count() + 1,
+    before[line_start..].chars().count() + 1,
+  )
+}
+
+fn byte_range(node: &Node) -> Option<Range<usize>> {
+  node.srcmap.map(|pos| {
+    let (start, end) = pos.get_byte_offsets();
+    start..end
+  })
+}
+
+/// Byte ranges of code blocks, where markdown syntax isn't parsed.
+fn code_ranges(ast: &Node) -> Vec<Range<usize>> {
+  let mut ranges = vec![];
+  ast.walk(|node, _| {
+    if node.is::<HighlightedCode>()
+      && let Some(range) = byte_range(node)
+    {
+      ranges.push(range);
+    }
+  });
+  ranges
+}
+
+/// `## A` followed by `#### B`. Fixed by lowering the level of ATX headings. Levels are compared
+/// with the previous heading as it is after its own fix, so one pass of fixes is enough.
+fn heading_increment(document: &Document) -> Vec<Finding> {
+  let mut findings = vec![];
+  let mut previous: Option<u8> = None;
+  document.ast.walk(|node, _| {
+    let (level, atx) = if let Some(heading) = node.cast::<ATXHeading>() {
+      (heading.level, true)
+    } else if let Some(heading) = node.cast::<SetextHeader>() {
+      (heading.level, false)
+    } else {
+      return;
+    };
+    let Some(start) = byte_range(node).map(|r| r.start) else {
+      return;
+    };
+
+    let mut effective = level;
+    if let Some(previous) = previous
+      && level > previous + 1
+    {
+      let mut finding = Finding::new(
+        start,
+        format!("heading level jumps from {} to {}", previous, level),
+      );
+      if atx {
+        effective = previous + 1;
+        let marker = start..start + level as usize;
+        finding = finding.with_fix(marker, "#".repeat(effective as usize));
+      }
+      findings.push(finding);
+    }
+    previous = Some(effective);
+  });
+  findings
+}
+
+fn image_alt_text(document: &Document) -> Vec<Finding> {
+  let mut findings = vec![];
+  document.ast.walk(|node, _| {
+    let url = if let Some(image) = node.cast::<ProcessedImage>() {
+      &image.url
+    } else if let Some(image) = node.cast::<Image>() {
+      &image.url
+    } else if let Some(image) = node.cast::<LazyLoadImage>() {
+      &image.cmark_image.url
+    } else {
+      return;
+    };
+    if node.collect_text().trim().is_empty()
+      && let Some(range) = byte_range(node)
+    {
+      findings.push(Finding::new(
+        range.start,
+        format!("image `{}` has no alt text", url),
+      ));
+    }
+  });
+  findings
+}
+
+/// Urls in text, which aren't linked. Fixed by turning them into autolinks.
+fn no_bare_urls(document: &Document) -> Vec<Finding> {
+  fn walk(node: &Node, source: &str, findings: &mut Vec<Finding>) {
+    if node.is::<Link>()
+      || node.is::<Autolink>()
+      || node.is::<CodeInline>()
+      || node.is::<ProcessedImage>()
+      || node.is::<Image>()
+      || node.is::<LazyLoadImage>()
+    {
+      return;
+    }
+    if node.is::<Text>()
+      && let Some(range) = byte_range(node)
+    {
+      for url in BARE_URL.find_iter(&source[range.clone()]) {
+        let url = url
+          .as_str()
+          .trim_end_matches(['.', ',', ';', ':', '!', '?']);
+        let start = range.start + (url.as_ptr() as usize - source[range.clone()].as_ptr() as usize);
+        findings.push(
+          Finding::new(start, format!("bare url `{}`", url))
+            .with_fix(start..start + url.len(), format!("<{}>", url)),
+        );
+      }
+    }
+    for child in node.children.iter() {
+      walk(child, source, findings);
+    }
+  }
+
+  let mut findings = vec![];
+  walk(document.ast, document.source, &mut findings);
+  findings
+}
+
+/// Whitespace at the end of lines outside of code blocks, except a hard break of exactly two
+/// spaces. Longer runs of spaces are hard breaks too, so they are fixed to two spaces.
+fn no_trailing_spaces(document: &Document) -> Vec<Finding> {
+  let code = code_ranges(document.ast);
+  let mut findings = vec![];
+  let mut offset = 0;
+  for line in document.source.split_inclusive('\n') {
+    let content = line.trim_end_matches(['\n', '\r']);
+    let trimmed = content.trim_end_matches([' ', '\t']);
+    let trailing = &content[trimmed.len()..];
+    if !trailing.is_empty()
+      && (trimmed.is_empty() || trailing != "  ")
+      && !code.iter().any(|r| r.contains(&offset))
+    {
+      let start = offset + trimmed.len();
+      let hard_break =
+        !trimmed.is_empty() && trailing.len() > 2 && trailing.bytes().all(|b| b == b' ');
+      let fix = if hard_break { "  " } else { "" };
+      findings.push(
+        Finding::new(start, "trailing whitespace".to_string())
+          .with_fix(start..start + trailing.len(), fix.to_string()),
+      );
+    }
+    offset += line.len();
+  }
+  findings
+}
+
+fn unclosed_hexo_tag(document: &Document) -> Vec<Finding> {
+  tag_plugin::find_unclosed(document.source)
+    .into_iter()
+    .map(|(offset, name)| {
+      let message = if name.is_empty() {
+        "`{%` is not closed with `%}`".to_string()
+      } else {
+        format!("`{}` is not closed with `{{% end{} %}}`", name, name)
+      };
+      Finding::new(offset, message)
+    })
+    .collect()
+}
+
+fn unknown_fence_language(document: &Document) -> Vec<Finding> {
+  let mut findings = vec![];
+  document.ast.walk(|node, _| {
+    let Some(code) = node.cast::<HighlightedCode>() else {
+      return;
+    };
+    if let Some(lang) = &code.lang
+      && !highlight::is_known_language(lang, document.diagram_languages)
+      && let Some(range) = byte_range(node)
+    {
+      findings.push(Finding::new(
+        range.start,
+        format!("unknown language `{}`, highlighted as plain text", lang),
+      ));
+    }
+  });
+  findings
+}
+
+/// Only the first definition of a label is rendered.
+fn duplicate_footnote_label(document: &Document) -> Vec<Finding> {
+  if !document.footnotes {
+    return vec![];
+  }
+  let code = code_ranges(document.ast);
+  let mut labels = HashSet::new();
+  FOOTNOTE_DEFINITION
+    .captures_iter(document.source)
+    .filter_map(|caps| {
+      let definition = caps.get(0)?;
+      let offset = definition.start() + (definition.len() - definition.as_str().trim_start().len());
+      if code.iter().any(|r| r.contains(&offset)) || labels.insert(caps[1].to_string()) {
+        return None;
+      }
+      Some(Finding::new(
+        offset,
+        format!("footnote `{}` is already defined", &caps[1]),
+      ))
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use markdown_it::MarkdownIt;
+
+  fn lint(input: &str) -> Vec<LintDiagnostic> {
+    let mut md = MarkdownIt::new();
+    markdown_it::plugins::cmark::add(&mut md);
+    markdown_it_footnote::add(&mut md);
+    super::super::fence::add(&mut md);
+
+    let offset = front_matter_len(input);
+    let ast = md.parse(&input[offset..]);
+    let document = Document {
+      source: &input[offset..],
+      ast: &ast,
+      footnotes: true,
+      diagram_languages: None,
+    };
+    check(input, offset, &document)
+  }
+
+  fn rules(diagnostics: &[LintDiagnostic]) -> Vec<(&str, usize, usize)> {
+    diagnostics
+      .iter()
+      .map(|d| (d.rule, d.line, d.column))
+      .collect()
+  }
+
+  #[test]
+  fn test_heading_increment() {
+    let input = "# A\n\n### B\n\n## C\n\n> ##### D";
+    let diagnostics = lint(input);
+    assert_eq!(
+      rules(&diagnostics),
+      vec![("heading-increment", 3, 1), ("heading-increment", 7, 3)]
+    );
+    assert_eq!(diagnostics[0].message, "heading level jumps from 1 to 3");
+    assert_eq!(
+      apply_fixes(input, &diagnostics),
+      "# A\n\n## B\n\n## C\n\n> ### D"
+    );
+
+    let input = "# A\n\n### B\n\n#### C";
+    let diagnostics = lint(input);
+    assert_eq!(
+      rules(&diagnostics),
+      vec![("heading-increment", 3, 1), ("heading-increment", 5, 1)]
+    );
+    assert_eq!(diagnostics[1].message, "heading level jumps from 2 to 4");
+    let fixed = apply_fixes(input, &diagnostics);
+    assert_eq!(fixed, "# A\n\n## B\n\n### C");
+    assert_eq!(lint(&fixed), vec![]);
+  }
+
+  #[test]
+  fn test_image_alt_text() {
+    let diagnostics = lint("![](a.png) ![ ](b.png) ![B](b.png)");
+    assert_eq!(
+      rules(&diagnostics),
+      vec![("image-alt-text", 1, 1), ("image-alt-text", 1, 12)]
+    );
+    assert_eq!(diagnostics[0].message, "image `a.png` has no alt text");
+  }
+
+  #[test]
+  fn test_no_bare_urls() {
+    let input = "See https://example.com/a, [https://example.com/b](https://example.com/b) <https://example.com/c>\n\n`https://example.com/d`";
+    let diagnostics = lint(input);
+    assert_eq!(rules(&diagnostics), vec![("no-bare-urls", 1, 5)]);
+    assert_eq!(
+      apply_fixes(input, &diagnostics),
+      input.replace("https://example.com/a,", "<https://example.com/a>,")
+    );
+  }
+
+  #[test]
+  fn test_no_trailing_spaces() {
+    let input = "hard break  \nspace \n  \nlong break    \ntab\t\n\n```\ncode \n```";
+    let diagnostics = lint(input);
+    assert_eq!(
+      rules(&diagnostics),
+      vec![
+        ("no-trailing-spaces", 2, 6),
+        ("no-trailing-spaces", 3, 1),
+        ("no-trailing-spaces", 4, 11),
+        ("no-trailing-spaces", 5, 4)
+      ]
+    );
+    assert_eq!(
+      apply_fixes(input, &diagnostics),
+      "hard break  \nspace\n\nlong break  \ntab\n\n```\ncode \n```"
+    );
+  }
+
+  #[test]
+  fn test_unclosed_hexo_tag() {
+    let diagnostics = lint("text\n\n{% blockquote %}\nquote");
+    assert_eq!(rules(&diagnostics), vec![("unclosed-hexo-tag", 3, 1)]);
+    assert_eq!(diagnostics[0].severity, Severity::Error);
+    assert_eq!(
+      diagnostics[0].message,
+      "`blockquote` is not closed with `{% endblockquote %}`"
+    );
+  }
+
+  #[test]
+  fn test_unknown_fence_language() {
+    let diagnostics = lint("```rust\n```\n\n```mermaid\n```\n\n```rustt\n```\n\n```\n```");
+    assert_eq!(rules(&diagnostics), vec![("unknown-fence-language", 7, 1)]);
+    assert_eq!(
+      diagnostics[0].message,
+      "unknown language `rustt`, highlighted as plain text"
+    );
+  }
+
+  #[test]
+  fn test_duplicate_footnote_label() {
+    let input = "a[^1]\n\n[^1]: one\n\n```\n[^1]: code\n```\n\n [^1]: two";
+    let diagnostics = lint(input);
+    assert_eq!(
+      rules(&diagnostics),
+      vec![("duplicate-footnote-label", 9, 2)]
+    );
+    assert_eq!(diagnostics[0].message, "footnote `1` is already defined");
+  }
+
+  #[test]
+  fn test_front_matter() {
+    let input = "---\ntitle: a  \n---\n# A\n\n### B";
+    assert_eq!(front_matter_len(input), 19);
+    assert_eq!(rules(&lint(input)), vec![("heading-increment", 6, 1)]);
+
+    assert_eq!(front_matter_len("---\nnot closed"), 0);
+    assert_eq!(front_matter_len("# A\n---\n"), 0);
+  }
+}
@@ -0,0 +1,315 @@
+use latex2mathml::{DisplayStyle, latex_to_mathml};
+use markdown_it::common::sourcemap::SourceWithLineStarts;
+use markdown_it::parser::block::{BlockRule, BlockState};
+use markdown_it::parser::core::Root;
+use markdown_it::parser::inline::{InlineRule, InlineState};
+use markdown_it::{MarkdownIt, Node, NodeValue, Renderer};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MathError {
+  /// 1-based line number of the expression in the markdown source.
+  pub line: usize,
+  pub source: String,
+  pub message: String,
+}
+
+#[derive(Debug)]
+pub struct Math {
+  pub latex: String,
+  pub display: bool,
+  pub result: Result<String, String>,
+}
+
+impl Math {
+  fn new(latex: &str, display: bool) -> Self {
+    let style = if display {
+      DisplayStyle::Block
+    } else {
+      DisplayStyle::Inline
+    };
+    Self {
+      latex: latex.to_string(),
+      display,
+      result: to_mathml(latex, style),
+    }
+  }
+}
+
+impl NodeValue for Math {
+  fn render(&self, node: &Node, fmt: &mut dyn Renderer) {
+    if self.display {
+      fmt.cr();
+    }
+    match &self.result {
+      Ok(mathml) => fmt.text_raw(mathml),
+      Err(_) => {
+        let mut attrs = node.attrs.clone();
+        attrs.push(("class", "math".to_string()));
+        fmt.open("span", &attrs);
+        fmt.text(&self.latex);
+        fmt.close("span");
+      }
+    }
+    if self.display {
+      fmt.cr();
+    }
+  }
+}
+
+/// Converts LaTeX to MathML. The converter is not hardened against every malformed input,
+/// so a panic is reported as a parse error instead of aborting the whole render.
+fn to_mathml(latex: &str, style: DisplayStyle) -> Result<String, String> {
+  if latex.trim().is_empty() {
+    return Err("empty expression".to_string());
+  }
+  match std::panic::catch_unwind(|| latex_to_mathml(latex, style)) {
+    Ok(Ok(mathml)) => Ok(escape_text(&mathml)),
+    Ok(Err(e)) => Err(e.to_string()),
+    Err(_) => Err(format!("failed to parse `{}`", latex)),
+  }
+}
+
+/// latex2mathml writes operators such as `<` into the markup as is (`<mo><</mo>`),
+/// so escape `<`, `>` and `&` that are not part of a tag or an entity.
+fn escape_text(mathml: &str) -> String {
+  let mut out = String::with_capacity(mathml.len());
+  let mut in_tag = false;
+  for (i, c) in mathml.char_indices() {
+    let rest = &mathml[i + c.len_utf8()..];
+    match c {
+      '<' if !in_tag && rest.starts_with(|c: char| c.is_ascii_alphabetic() || c == '/') => {
+        in_tag = true;
+        out.push(c);
+      }
+      '>' if in_tag => {
+        in_tag = false;
+        out.push(c);
+      }
+      '<' if !in_tag => out.push_str("&lt;"),
+      '>' => out.push_str("&gt;"),
+      '&' if !in_tag && !is_entity(rest) => out.push_str("&amp;"),
+      _ => out.push(c),
+    }
+  }
+  out
+}
+
+fn is_entity(s: &str) -> bool {
+  let Some(end) = s.find(';') else {
+    return false;
+  };
+  let name = s[..end].strip_prefix('#').unwrap_or(&s[..end]);
+  !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric())
+}
+
+/// Adds `$...$` (inline) and `$$...$$` (display) math rendered to MathML.
+///
+/// Expressions that fail to parse are rendered as escaped source in `<span class="math">`
+/// and can be collected with [`collect_errors`].
+///
+/// # Examples
+///
+/// ```
+/// // Input:
+/// // $x^2$
+///
+/// // Rendered output:
+/// // <p><math xmlns="http://www.w3.org/1998/Math/MathML" display="inline"><msup><mi>x</mi><mn>2</mn></msup></math></p>
+/// ```
+pub fn add(md: &mut MarkdownIt) {
+  md.inline.add_rule::<MathInlineScanner>();
+  md.block.add_rule::<MathBlockScanner>();
+}
+
+/// Returns parse errors of all math expressions in the AST.
+pub fn collect_errors(root: &Node) -> Vec<MathError> {
+  let Some(source) = root.cast::<Root>().map(|r| &r.content) else {
+    return Vec::new();
+  };
+  let map = SourceWithLineStarts::new(source);
+
+  let mut errors = Vec::new();
+  root.walk(|node, _| {
+    let Some(math) = node.cast::<Math>() else {
+      return;
+    };
+    if let Err(message) = &math.result {
+      let line = node
+        .srcmap
+        .map(|pos| pos.get_positions(&map).0.0 as usize)
+        .unwrap_or_default();
+      errors.push(MathError {
+        line,
+        source: math.latex.clone(),
+        message: message.clone(),
+      });
+    }
+  });
+  errors
+}
+
+struct MathInlineScanner;
+
+impl InlineRule for MathInlineScanner {
+  const MARKER: char = '$';
+
+  fn run(state: &mut InlineState) -> Option<(Node, usize)> {
+    let src = &state.src[state.pos..state.pos_max];
+    if !src.starts_with('$') {
+      return None;
+    }
+    let display = src.starts_with("$$");
+    let marker = if display { "$$" } else { "$" };
+    let content = &src[marker.len()..];
+
+    // same as pandoc: no whitespace right after the opening `$`
+    if !display && content.starts_with(char::is_whitespace) {
+      return None;
+    }
+
+    let mut offset = 0;
+    loop {
+      let end = offset + content[offset..].find(marker)?;
+      if content[..end].ends_with('\\') {
+        offset = end + 1;
+        continue;
+      }
+      if !display {
+        // no whitespace right before the closing `$`, and no digit right after it (e.g. `$5 and $6`)
+        let next = content[end + 1..].chars().next();
+        if content[..end].ends_with(char::is_whitespace) || next.is_some_and(|c| c.is_ascii_digit())
+        {
+          offset = end + 1;
+          continue;
+        }
+      }
+      if end == 0 {
+        return None;
+      }
+
+      let latex = &content[..end];
+      let node = Node::new(Math::new(latex, display));
+      return Some((node, marker.len() * 2 + end));
+    }
+  }
+}
+
+struct MathBlockScanner;
+
+impl BlockRule for MathBlockScanner {
+  fn run(state: &mut BlockState) -> Option<(Node, usize)> {
+    if state.line_indent(state.line) >= state.md.max_indent {
+      return None;
+    }
+
+    let first = state.get_line(state.line).trim_end();
+    let first = first.strip_prefix("$$")?;
+
+    // single line: $$ ... $$
+    if let Some(latex) = first.strip_suffix("$$") {
+      if latex.trim().is_empty() {
+        return None;
+      }
+      return Some((Node::new(Math::new(latex.trim(), true)), 1));
+    }
+
+    let start_line = state.line;
+    let mut next_line = start_line + 1;
+    let mut lines = vec![first.to_string()];
+    let mut have_end_marker = false;
+    while next_line < state.line_max {
+      if !state.is_empty(next_line) && state.line_indent(next_line) < 0 {
+        break;
+      }
+      let line = state.get_line(next_line).trim_end();
+      if let Some(last) = line.strip_suffix("$$") {
+        lines.push(last.to_string());
+        have_end_marker = true;
+        break;
+      }
+      lines.push(line.to_string());
+      next_line += 1;
+    }
+    if !have_end_marker {
+      return None;
+    }
+
+    let latex = lines.join("\n");
+    Some((
+      Node::new(Math::new(latex.trim(), true)),
+      next_line - start_line + 1,
+    ))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn parser() -> MarkdownIt {
+    let mut md = MarkdownIt::new();
+    markdown_it::plugins::cmark::add(&mut md);
+    add(&mut md);
+    md
+  }
+
+  #[test]
+  fn test_inline_math() {
+    let output = parser().parse("$x^2$").render();
+    assert!(
+      output
+        .starts_with("<p><math xmlns=\"http://www.w3.org/1998/Math/MathML\" display=\"inline\">")
+    );
+    assert!(output.contains("<msup><mi>x</mi><mn>2</mn></msup>"));
+  }
+
+  #[test]
+  fn test_dollar_amounts_are_not_math() {
+    let output = parser().parse("It costs $5 and $6.").render();
+    assert_eq!(output, "<p>It costs $5 and $6.</p>\n");
+  }
+
+  #[test]
+  fn test_math_after_multibyte_text() {
+    let output = parser().parse("値は「$x$」です").render();
+    assert!(output.starts_with("<p>値は「<math"));
+  }
+
+  #[test]
+  fn test_block_math() {
+    let output = parser().parse("$$\nx = 1\n$$").render();
+    assert!(
+      output.starts_with("<math xmlns=\"http://www.w3.org/1998/Math/MathML\" display=\"block\">")
+    );
+  }
+
+  #[test]
+  fn test_invalid_math_falls_back_to_source() {
+    let md = parser();
+    let ast = md.parse("text\n\n$\\frac{a<b$");
+    assert_eq!(
+      ast.render(),
+      "<p>text</p>\n<p><span class=\"math\">\\frac{a&lt;b</span></p>\n"
+    );
+
+    let errors = collect_errors(&ast);
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].line, 3);
+    assert_eq!(errors[0].source, "\\frac{a<b");
+  }
+
+  #[test]
+  fn test_operators_are_escaped() {
+    let output = parser().parse("$a < b \\langle$").render();
+    assert!(output.contains("<mo>&lt;</mo>"));
+    assert!(output.contains("&lang;"));
+  }
+
+  #[test]
+  fn test_escape_text() {
+    assert_eq!(
+      escape_text("<math><mo><</mo><mo>></mo><mo>&</mo><mo>&lang;</mo></math>"),
+      "<math><mo>&lt;</mo><mo>&gt;</mo><mo>&amp;</mo><mo>&lang;</mo></math>"
+    );
+  }
+}
@@ -0,0 +1,167 @@
+use markdown_it::Node;
+use markdown_it::parser::core::Root;
+use markdown_it::parser::inline::{Text, TextSpecial};
+use markdown_it::plugins::cmark::block::code::CodeBlock;
+use markdown_it::plugins::cmark::block::fence::CodeFence;
+use markdown_it::plugins::cmark::block::heading::ATXHeading;
+use markdown_it::plugins::cmark::block::lheading::SetextHeader;
+use markdown_it::plugins::cmark::block::list::ListItem;
+use markdown_it::plugins::cmark::block::paragraph::Paragraph;
+use markdown_it::plugins::cmark::inline::image::Image;
+use markdown_it::plugins::cmark::inline::newline::{Hardbreak, Softbreak};
+use markdown_it::plugins::extra::tables::{TableCell, TableRow};
+use markdown_it::plugins::html::html_block::HtmlBlock;
+use markdown_it::plugins::html::html_inline::HtmlInline;
+use markdown_it_lazyload::LazyLoadImage;
+
+use super::cjk::is_cjk;
+use super::fence::HighlightedCode;
+use super::image::ProcessedImage;
+use super::ruby::Ruby;
+
+// Average silent reading speeds
+const LATIN_WORDS_PER_MINUTE: usize = 200;
+const CJK_CHARS_PER_MINUTE: usize = 500;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Meta {
+  pub plain_text: String,
+  pub excerpt: String,
+  pub character_count: usize,
+  pub word_count: usize,
+  pub reading_time_minutes: usize,
+}
+
+/// Collects plain text and text statistics from the AST.
+///
+/// Code blocks, raw HTML and images are not part of the plain text.
+pub fn collect(root: &Node) -> Meta {
+  let mut plain_text = String::new();
+  collect_text(root, &mut plain_text);
+  let plain_text = plain_text.trim().to_string();
+
+  let excerpt = root
+    .children
+    .iter()
+    .find(|node| node.is::<Paragraph>())
+    .map(|node| {
+      let mut s = String::new();
+      collect_text(node, &mut s);
+      s.trim().to_string()
+    })
+    .unwrap_or_default();
+
+  let character_count = plain_text.chars().filter(|c| !c.is_whitespace()).count();
+  let (latin_words, cjk_chars) = count_words(&plain_text);
+  let word_count = latin_words + cjk_chars;
+  let reading_time_minutes = if word_count == 0 {
+    0
+  } else {
+    let minutes = latin_words as f64 / LATIN_WORDS_PER_MINUTE as f64
+      + cjk_chars as f64 / CJK_CHARS_PER_MINUTE as f64;
+    (minutes.ceil() as usize).max(1)
+  };
+
+  Meta {
+    plain_text,
+    excerpt,
+    character_count,
+    word_count,
+    reading_time_minutes,
+  }
+}
+
+fn collect_text(node: &Node, out: &mut String) {
+  if let Some(text) = node.cast::<Text>() {
+    out.push_str(&text.content);
+    return;
+  }
+  if let Some(text) = node.cast::<TextSpecial>() {
+    out.push_str(&text.content);
+    return;
+  }
+  if let Some(ruby) = node.cast::<Ruby>() {
+    // annotations are readings of the base text, so they are not counted
+    for (base, _) in ruby.pairs.iter() {
+      out.push_str(base);
+    }
+    return;
+  }
+  if node.is::<Softbreak>() || node.is::<Hardbreak>() {
+    out.push(' ');
+    return;
+  }
+  if is_skipped(node) {
+    return;
+  }
+
+  for child in node.children.iter() {
+    collect_text(child, out);
+  }
+
+  if node.is::<TableCell>() {
+    out.push(' ');
+  } else if is_block(node) && !out.ends_with('\n') {
+    out.push('\n');
+  }
+}
+
+/// Nodes that never contribute to plain text.
+fn is_skipped(node: &Node) -> bool {
+  node.is::<CodeBlock>()
+    || node.is::<CodeFence>()
+    || node.is::<HighlightedCode>()
+    || node.is::<HtmlBlock>()
+    || node.is::<HtmlInline>()
+    || node.is::<Image>()
+    || node.is::<LazyLoadImage>()
+    || node.is::<ProcessedImage>()
+}
+
+fn is_block(node: &Node) -> bool {
+  node.is::<Paragraph>()
+    || node.is::<ATXHeading>()
+    || node.is::<SetextHeader>()
+    || node.is::<ListItem>()
+    || node.is::<TableRow>()
+    || node.is::<Root>()
+}
+
+/// Returns `(latin_words, cjk_chars)`.
+///
+/// Each CJK character counts as a word since CJK text has no word separators.
+fn count_words(text: &str) -> (usize, usize) {
+  let mut latin_words = 0;
+  let mut cjk_chars = 0;
+  let mut in_word = false;
+
+  for c in text.chars() {
+    if is_cjk(c) {
+      cjk_chars += 1;
+      in_word = false;
+    } else if c.is_alphanumeric() {
+      if !in_word {
+        latin_words += 1;
+      }
+      in_word = true;
+    } else if c.is_whitespace() || !matches!(c, '\'' | '’' | '-' | '_' | '.') {
+      in_word = false;
+    }
+  }
+
+  (latin_words, cjk_chars)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_count_words() {
+    assert_eq!(count_words("Hello world"), (2, 0));
+    assert_eq!(count_words("It's a well-known fact."), (4, 0));
+    assert_eq!(count_words("日本語の文章"), (0, 6));
+    assert_eq!(count_words("Rustで書く。"), (1, 3));
+    assert_eq!(count_words(""), (0, 0));
+  }
+}
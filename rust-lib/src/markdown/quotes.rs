@@ -0,0 +1,112 @@
+use markdown_it::parser::core::CoreRule;
+use markdown_it::parser::extset::MarkdownItExt;
+use markdown_it::parser::inline::Text;
+use markdown_it::plugins::extra::smartquotes::{self, SmartQuotesRule};
+use markdown_it::{MarkdownIt, Node};
+
+/// `“”‘’`, in the same order as the markdown-it (JS) `quotes` option:
+/// double open, double close, single open, single close.
+pub const DEFAULT_QUOTES: [char; 4] = ['“', '”', '‘', '’'];
+
+// smartquotes takes its characters as const generics, so non-default quotes are
+// rendered as these private use characters first and replaced afterwards.
+const OPEN_SINGLE: char = '\u{E000}';
+const CLOSE_SINGLE: char = '\u{E001}';
+const OPEN_DOUBLE: char = '\u{E002}';
+const CLOSE_DOUBLE: char = '\u{E003}';
+
+/// The smartquotes rule used for non-default quotes.
+pub type CustomSmartQuotesRule =
+  SmartQuotesRule<OPEN_SINGLE, CLOSE_SINGLE, OPEN_DOUBLE, CLOSE_DOUBLE>;
+
+#[derive(Debug, Clone, Copy)]
+struct Quotes([char; 4]);
+
+impl MarkdownItExt for Quotes {}
+
+/// Adds smartquotes with the given `[double open, double close, single open, single close]` quotes.
+///
+/// # Examples
+///
+/// ```
+/// // quotes: 「」『』
+///
+/// // Input:
+/// // "Hello" 'world'
+///
+/// // Rendered output:
+/// // <p>「Hello」 『world』</p>
+/// ```
+pub fn add(md: &mut MarkdownIt, quotes: [char; 4]) {
+  if quotes == DEFAULT_QUOTES {
+    smartquotes::add(md);
+    return;
+  }
+
+  md.ext.insert(Quotes(quotes));
+  smartquotes::add_with::<OPEN_SINGLE, CLOSE_SINGLE, OPEN_DOUBLE, CLOSE_DOUBLE>(md);
+  md.add_rule::<QuotesRule>().after::<CustomSmartQuotesRule>();
+}
+
+struct QuotesRule;
+
+impl CoreRule for QuotesRule {
+  fn run(root: &mut Node, md: &MarkdownIt) {
+    let Some(Quotes([open_double, close_double, open_single, close_single])) =
+      md.ext.get::<Quotes>().copied()
+    else {
+      return;
+    };
+
+    root.walk_mut(|node, _| {
+      let Some(text) = node.cast_mut::<Text>() else {
+        return;
+      };
+      if !text
+        .content
+        .contains([OPEN_SINGLE, CLOSE_SINGLE, OPEN_DOUBLE, CLOSE_DOUBLE])
+      {
+        return;
+      }
+      text.content = text
+        .content
+        .chars()
+        .map(|c| match c {
+          OPEN_SINGLE => open_single,
+          CLOSE_SINGLE => close_single,
+          OPEN_DOUBLE => open_double,
+          CLOSE_DOUBLE => close_double,
+          _ => c,
+        })
+        .collect();
+    });
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn parser(quotes: [char; 4]) -> MarkdownIt {
+    let mut md = MarkdownIt::new();
+    markdown_it::plugins::cmark::add(&mut md);
+    add(&mut md, quotes);
+    md
+  }
+
+  #[test]
+  fn test_default_quotes() {
+    let output = parser(DEFAULT_QUOTES)
+      .parse("\"Hello\" 'world' it's")
+      .render();
+    assert_eq!(output, "<p>“Hello” ‘world’ it’s</p>\n");
+  }
+
+  #[test]
+  fn test_japanese_quotes() {
+    let output = parser(['「', '」', '『', '』'])
+      .parse("\"Hello\" 'world' it's")
+      .render();
+    assert_eq!(output, "<p>「Hello」 『world』 it’s</p>\n");
+  }
+}
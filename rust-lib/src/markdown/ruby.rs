@@ -0,0 +1,187 @@
+use markdown_it::generics::inline::full_link::LinkScanner;
+use markdown_it::parser::inline::{InlineRule, InlineState};
+use markdown_it::{MarkdownIt, Node, NodeValue, Renderer};
+
+#[derive(Debug)]
+pub struct Ruby {
+  /// `(base, annotation)` pairs. More than one pair for per-character annotations.
+  pub pairs: Vec<(String, String)>,
+}
+
+impl NodeValue for Ruby {
+  fn render(&self, node: &Node, fmt: &mut dyn Renderer) {
+    fmt.open("ruby", &node.attrs);
+    for (base, annotation) in self.pairs.iter() {
+      fmt.open("rb", &[]);
+      fmt.text(base);
+      fmt.close("rb");
+      fmt.open("rp", &[]);
+      fmt.text("(");
+      fmt.close("rp");
+      fmt.open("rt", &[]);
+      fmt.text(annotation);
+      fmt.close("rt");
+      fmt.open("rp", &[]);
+      fmt.text(")");
+      fmt.close("rp");
+    }
+    fmt.close("ruby");
+  }
+}
+
+/// Adds ruby annotations: `{漢字|かんじ}` and `[漢字]^(かんじ)`.
+///
+/// `{漢字|かん|じ}` annotates each character when the number of annotations matches
+/// the number of characters.
+///
+/// # Examples
+///
+/// ```
+/// // Input:
+/// // {漢字|かんじ}
+///
+/// // Rendered output:
+/// // <p><ruby><rb>漢字</rb><rp>(</rp><rt>かんじ</rt><rp>)</rp></ruby></p>
+/// ```
+pub fn add(md: &mut MarkdownIt) {
+  md.inline.add_rule::<BraceRubyScanner>();
+  md.inline
+    .add_rule::<BracketRubyScanner>()
+    .before::<LinkScanner<false>>();
+}
+
+fn new_ruby(base: &str, annotations: &[&str]) -> Option<Node> {
+  if base.trim().is_empty() || annotations.iter().any(|a| a.trim().is_empty()) {
+    return None;
+  }
+
+  let chars: Vec<char> = base.chars().collect();
+  let pairs = if annotations.len() > 1 && annotations.len() == chars.len() {
+    chars
+      .iter()
+      .zip(annotations)
+      .map(|(c, a)| (c.to_string(), a.to_string()))
+      .collect()
+  } else {
+    vec![(base.to_string(), annotations.join(""))]
+  };
+  Some(Node::new(Ruby { pairs }))
+}
+
+/// `{base|annotation}`
+struct BraceRubyScanner;
+
+impl InlineRule for BraceRubyScanner {
+  const MARKER: char = '{';
+
+  fn run(state: &mut InlineState) -> Option<(Node, usize)> {
+    let src = &state.src[state.pos..state.pos_max];
+    if !src.starts_with('{') {
+      return None;
+    }
+    let end = src.find('}')?;
+    let inner = &src[1..end];
+    if inner.contains(['{', '\n']) {
+      return None;
+    }
+
+    let mut parts = inner.split('|');
+    let base = parts.next()?;
+    let annotations: Vec<&str> = parts.collect();
+    if annotations.is_empty() {
+      return None;
+    }
+
+    let node = new_ruby(base, &annotations)?;
+    Some((node, end + 1))
+  }
+}
+
+/// `[base]^(annotation)`
+struct BracketRubyScanner;
+
+impl InlineRule for BracketRubyScanner {
+  const MARKER: char = '[';
+
+  fn run(state: &mut InlineState) -> Option<(Node, usize)> {
+    let src = &state.src[state.pos..state.pos_max];
+    if !src.starts_with('[') {
+      return None;
+    }
+    let base_end = src.find(']')?;
+    let base = &src[1..base_end];
+    if base.contains(['[', '\n']) || base.starts_with('^') {
+      return None;
+    }
+
+    let rest = src[base_end + 1..].strip_prefix("^(")?;
+    let annotation_end = rest.find(')')?;
+    let annotation = &rest[..annotation_end];
+    if annotation.contains(['(', '\n']) {
+      return None;
+    }
+
+    let node = new_ruby(base, &[annotation])?;
+    Some((node, base_end + 1 + 2 + annotation_end + 1))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn parser() -> MarkdownIt {
+    let mut md = MarkdownIt::new();
+    markdown_it::plugins::cmark::add(&mut md);
+    add(&mut md);
+    md
+  }
+
+  #[test]
+  fn test_brace_ruby() {
+    let output = parser().parse("{漢字|かんじ}を読む").render();
+    assert_eq!(
+      output,
+      "<p><ruby><rb>漢字</rb><rp>(</rp><rt>かんじ</rt><rp>)</rp></ruby>を読む</p>\n"
+    );
+  }
+
+  #[test]
+  fn test_brace_ruby_per_character() {
+    let output = parser().parse("{漢字|かん|じ}").render();
+    assert_eq!(
+      output,
+      "<p><ruby><rb>漢</rb><rp>(</rp><rt>かん</rt><rp>)</rp><rb>字</rb><rp>(</rp><rt>じ</rt><rp>)</rp></ruby></p>\n"
+    );
+  }
+
+  #[test]
+  fn test_bracket_ruby() {
+    let output = parser().parse("[東京]^(とうきょう)").render();
+    assert_eq!(
+      output,
+      "<p><ruby><rb>東京</rb><rp>(</rp><rt>とうきょう</rt><rp>)</rp></ruby></p>\n"
+    );
+  }
+
+  #[test]
+  fn test_ruby_is_escaped() {
+    let output = parser().parse("{<b>|<i>}").render();
+    assert_eq!(
+      output,
+      "<p><ruby><rb>&lt;b&gt;</rb><rp>(</rp><rt>&lt;i&gt;</rt><rp>)</rp></ruby></p>\n"
+    );
+  }
+
+  #[test]
+  fn test_not_ruby() {
+    let md = parser();
+    assert_eq!(md.parse("{text}").render(), "<p>{text}</p>\n");
+    assert_eq!(md.parse("{|x}").render(), "<p>{|x}</p>\n");
+    assert_eq!(
+      md.parse("[link](https://example.com)").render(),
+      "<p><a href=\"https://example.com\">link</a></p>\n"
+    );
+    assert_eq!(md.parse("[text]^ (x)").render(), "<p>[text]^ (x)</p>\n");
+  }
+}
@@ -0,0 +1,132 @@
+use markdown_it::parser::core::CoreRule;
+use markdown_it::parser::inline::Text;
+use markdown_it::plugins::cmark::block::list::{BulletList, ListItem, OrderedList};
+use markdown_it::plugins::cmark::block::paragraph::Paragraph;
+use markdown_it::{MarkdownIt, Node, NodeValue, Renderer};
+
+#[derive(Debug)]
+pub struct TaskCheckbox {
+  pub checked: bool,
+}
+
+impl NodeValue for TaskCheckbox {
+  fn render(&self, _: &Node, fmt: &mut dyn Renderer) {
+    let mut attrs = vec![("class", "task-list-item-checkbox".to_string())];
+    if self.checked {
+      attrs.push(("checked", String::new()));
+    }
+    attrs.push(("disabled", String::new()));
+    attrs.push(("type", "checkbox".to_string()));
+    fmt.self_close("input", &attrs);
+  }
+}
+
+/// Adds GFM task lists with the same output as markdown-it-task-lists.
+///
+/// # Examples
+///
+/// ```
+/// // Input:
+/// // - [ ] todo
+/// // - [x] done
+///
+/// // Rendered output:
+/// // <ul class="contains-task-list">
+/// // <li class="task-list-item"><input class="task-list-item-checkbox" disabled="" type="checkbox"> todo</li>
+/// // <li class="task-list-item"><input class="task-list-item-checkbox" checked="" disabled="" type="checkbox"> done</li>
+/// // </ul>
+/// ```
+pub fn add(md: &mut MarkdownIt) {
+  md.add_rule::<TaskListRule>();
+}
+
+struct TaskListRule;
+
+impl CoreRule for TaskListRule {
+  fn run(root: &mut Node, _: &MarkdownIt) {
+    root.walk_mut(|node, _| {
+      if !node.is::<BulletList>() && !node.is::<OrderedList>() {
+        return;
+      }
+
+      let mut contains_task = false;
+      for item in node.children.iter_mut() {
+        if item.is::<ListItem>() && convert_item(item) {
+          item.attrs.push(("class", "task-list-item".to_string()));
+          contains_task = true;
+        }
+      }
+      if contains_task {
+        node.attrs.push(("class", "contains-task-list".to_string()));
+      }
+    });
+  }
+}
+
+/// Replaces a leading `[ ] `, `[x] ` or `[X] ` of the item with a checkbox.
+fn convert_item(item: &mut Node) -> bool {
+  // loose items wrap their text in a paragraph, tight items don't
+  let inlines = match item.children.first() {
+    Some(first) if first.is::<Paragraph>() => &mut item.children[0].children,
+    _ => &mut item.children,
+  };
+
+  let Some(text) = inlines.first_mut().and_then(|n| n.cast_mut::<Text>()) else {
+    return false;
+  };
+  let checked = if text.content.starts_with("[ ] ") {
+    false
+  } else if text.content.starts_with("[x] ") || text.content.starts_with("[X] ") {
+    true
+  } else {
+    return false;
+  };
+
+  text.content.replace_range(..3, "");
+  inlines.insert(0, Node::new(TaskCheckbox { checked }));
+  true
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn parser() -> MarkdownIt {
+    let mut md = MarkdownIt::new();
+    markdown_it::plugins::cmark::add(&mut md);
+    add(&mut md);
+    md
+  }
+
+  #[test]
+  fn test_task_list() {
+    let output = parser().parse("- [ ] todo\n- [x] done\n- plain").render();
+    assert_eq!(
+      output,
+      "<ul class=\"contains-task-list\">\n\
+       <li class=\"task-list-item\"><input class=\"task-list-item-checkbox\" disabled=\"\" type=\"checkbox\"> todo</li>\n\
+       <li class=\"task-list-item\"><input class=\"task-list-item-checkbox\" checked=\"\" disabled=\"\" type=\"checkbox\"> done</li>\n\
+       <li>plain</li>\n\
+       </ul>\n"
+    );
+  }
+
+  #[test]
+  fn test_loose_task_list() {
+    let output = parser().parse("1. [X] done\n\n2. todo").render();
+    assert_eq!(
+      output,
+      "<ol class=\"contains-task-list\">\n\
+       <li class=\"task-list-item\">\n<p><input class=\"task-list-item-checkbox\" checked=\"\" disabled=\"\" type=\"checkbox\"> done</p>\n</li>\n\
+       <li>\n<p>todo</p>\n</li>\n\
+       </ol>\n"
+    );
+  }
+
+  #[test]
+  fn test_not_task_list() {
+    let md = parser();
+    assert_eq!(md.parse("- [ ]").render(), "<ul>\n<li>[ ]</li>\n</ul>\n");
+    assert_eq!(md.parse("[ ] text").render(), "<p>[ ] text</p>\n");
+  }
+}
@@ -0,0 +1,105 @@
+use markdown_it::Node;
+
+use super::anchor::heading_level;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TocItem {
+  pub level: u8,
+  pub text: String,
+  pub id: String,
+  pub children: Vec<TocItem>,
+}
+
+/// Collects headings from the AST and nests them by level.
+///
+/// A heading becomes a child of the closest preceding heading with a lower level.
+/// Skipped levels (e.g. `h2` followed by `h4`) are nested as-is without placeholders.
+pub fn collect(root: &Node) -> Vec<TocItem> {
+  let mut flat = Vec::new();
+
+  root.walk(|node, _| {
+    if let Some(level) = heading_level(node) {
+      let id = node
+        .attrs
+        .iter()
+        .find(|(name, _)| *name == "id")
+        .map(|(_, value)| value.clone())
+        .unwrap_or_default();
+
+      flat.push(TocItem {
+        level,
+        text: node.collect_text(),
+        id,
+        children: Vec::new(),
+      });
+    }
+  });
+
+  nest(flat)
+}
+
+fn nest(flat: Vec<TocItem>) -> Vec<TocItem> {
+  let mut result: Vec<TocItem> = Vec::new();
+  // stack of open items, each one is a child of the previous entry
+  let mut stack: Vec<TocItem> = Vec::new();
+
+  for item in flat {
+    while stack.last().is_some_and(|last| last.level >= item.level) {
+      close_last(&mut stack, &mut result);
+    }
+    stack.push(item);
+  }
+  while !stack.is_empty() {
+    close_last(&mut stack, &mut result);
+  }
+
+  result
+}
+
+fn close_last(stack: &mut Vec<TocItem>, result: &mut Vec<TocItem>) {
+  let item = stack.pop().unwrap();
+  match stack.last_mut() {
+    Some(parent) => parent.children.push(item),
+    None => result.push(item),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn item(level: u8, id: &str, children: Vec<TocItem>) -> TocItem {
+    TocItem {
+      level,
+      text: id.to_string(),
+      id: id.to_string(),
+      children,
+    }
+  }
+
+  #[test]
+  fn test_nest() {
+    let flat = vec![
+      item(2, "a", vec![]),
+      item(3, "a1", vec![]),
+      item(3, "a2", vec![]),
+      item(2, "b", vec![]),
+      item(4, "b1", vec![]),
+      item(1, "c", vec![]),
+    ];
+
+    assert_eq!(
+      nest(flat),
+      vec![
+        item(2, "a", vec![item(3, "a1", vec![]), item(3, "a2", vec![])]),
+        item(2, "b", vec![item(4, "b1", vec![])]),
+        item(1, "c", vec![]),
+      ]
+    );
+  }
+
+  #[test]
+  fn test_nest_empty() {
+    assert!(nest(Vec::new()).is_empty());
+  }
+}
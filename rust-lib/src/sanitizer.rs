@@ -0,0 +1,546 @@
+use ammonia::Url;
+use html5ever::tendril::StrTendril;
+use html5ever::tokenizer::states::RawKind;
+use html5ever::tokenizer::{BufferQueue, TagKind, Token, TokenSink, TokenSinkResult, Tokenizer};
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+/// Tags allowed by default. Covers what the markdown renderer and the tag plugins emit
+/// (footnotes, highlighted code, MathML, embeds) and common hand-written blog HTML.
+#[rustfmt::skip]
+const DEFAULT_TAGS: &[&str] = &[
+  "a", "abbr", "article", "aside", "audio", "b", "bdi", "bdo", "blockquote", "br", "button", "caption",
+  "cite", "code", "col", "colgroup", "data", "dd", "del", "details", "dfn", "div", "dl", "dt",
+  "em", "figcaption", "figure", "footer", "h1", "h2", "h3", "h4", "h5", "h6", "header", "hr",
+  "i", "iframe", "img", "input", "ins", "kbd", "li", "mark", "nav", "ol", "p", "picture", "pre", "q",
+  "rb", "rp", "rt", "ruby", "s", "samp", "section", "small", "source", "span", "strong", "sub",
+  "summary", "sup", "table", "tbody", "td", "tfoot", "th", "thead", "time", "tr", "u", "ul",
+  "var", "video", "wbr",
+  // MathML
+  "math", "annotation", "menclose", "merror", "mfrac", "mi", "mmultiscripts", "mn", "mo",
+  "mover", "mpadded", "mphantom", "mroot", "mrow", "ms", "mspace", "msqrt", "mstyle", "msub",
+  "msubsup", "msup", "mtable", "mtd", "mtext", "mtr", "munder", "munderover", "semantics",
+];
+
+const DEFAULT_GENERIC_ATTRIBUTES: &[&str] = &["class", "id", "lang", "title", "dir"];
+
+/// `data-*` and `aria-*` are always allowed.
+const GENERIC_ATTRIBUTE_PREFIXES: &[&str] = &["data-", "aria-"];
+
+#[rustfmt::skip]
+const DEFAULT_TAG_ATTRIBUTES: &[(&str, &[&str])] = &[
+  ("a", &["href", "hreflang", "rel", "target"]),
+  ("audio", &["src", "controls", "loop", "muted", "preload"]),
+  ("blockquote", &["cite"]),
+  // copy button of highlighted code
+  ("button", &["type"]),
+  ("col", &["span"]),
+  ("colgroup", &["span"]),
+  ("del", &["cite", "datetime"]),
+  ("details", &["open"]),
+  ("iframe", &["src", "width", "height", "allow", "allowfullscreen", "frameborder", "loading", "referrerpolicy"]),
+  ("img", &["src", "alt", "width", "height", "loading", "decoding", "srcset", "sizes"]),
+  // task list checkboxes
+  ("input", &["type", "checked", "disabled"]),
+  ("ins", &["cite", "datetime"]),
+  ("li", &["value"]),
+  ("math", &["display", "xmlns"]),
+  ("mfrac", &["linethickness"]),
+  ("mi", &["mathvariant"]),
+  ("mo", &["stretchy", "fence", "separator", "lspace", "rspace", "accent"]),
+  ("mover", &["accent"]),
+  ("mspace", &["width"]),
+  ("mstyle", &["displaystyle", "scriptlevel"]),
+  ("mtable", &["columnalign"]),
+  ("munder", &["accentunder"]),
+  ("ol", &["start", "reversed", "type"]),
+  ("q", &["cite"]),
+  ("source", &["src", "srcset", "sizes", "type", "media"]),
+  ("td", &["colspan", "rowspan", "headers", "style"]),
+  ("th", &["colspan", "rowspan", "headers", "scope", "style"]),
+  ("time", &["datetime"]),
+  ("video", &["src", "poster", "width", "height", "controls", "loop", "muted", "playsinline", "preload"]),
+];
+
+const DEFAULT_URL_SCHEMES: &[&str] = &["http", "https", "mailto", "tel"];
+
+/// Hosts an `<iframe>` may load, i.e. what the `youtube` tag plugin emits.
+const DEFAULT_IFRAME_HOSTS: &[&str] = &["www.youtube.com", "www.youtube-nocookie.com"];
+
+/// Only table alignment (`style="text-align:right"`) survives in `style` attributes.
+const STYLE_PROPERTIES: &[&str] = &["text-align"];
+
+/// Allowlist overrides. `None` fields fall back to the default blog-friendly set.
+#[derive(Debug, Clone, Default)]
+pub struct SanitizeOptions {
+  pub tags: Option<Vec<String>>,
+  /// Attributes allowed on every tag.
+  pub generic_attributes: Option<Vec<String>>,
+  /// Attributes allowed per tag, e.g. `{"a": ["href"]}`.
+  pub tag_attributes: Option<HashMap<String, Vec<String>>>,
+  pub url_schemes: Option<Vec<String>>,
+  /// Hosts allowed in `<iframe src>`.
+  pub iframe_hosts: Option<Vec<String>>,
+}
+
+/// Something the sanitizer removed from the HTML.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Stripped {
+  Tag {
+    tag: String,
+  },
+  Attribute {
+    tag: String,
+    attribute: String,
+  },
+  Url {
+    tag: String,
+    attribute: String,
+    url: String,
+  },
+  Style {
+    tag: String,
+    property: String,
+  },
+}
+
+struct Allowlist {
+  tags: HashSet<String>,
+  generic_attributes: HashSet<String>,
+  tag_attributes: HashMap<String, HashSet<String>>,
+  url_schemes: HashSet<String>,
+  iframe_hosts: HashSet<String>,
+}
+
+impl Allowlist {
+  fn new(options: &SanitizeOptions) -> Self {
+    fn to_set(values: &Option<Vec<String>>, default: &[&str]) -> HashSet<String> {
+      match values {
+        Some(values) => values.iter().map(|v| v.to_ascii_lowercase()).collect(),
+        None => default.iter().map(|v| v.to_string()).collect(),
+      }
+    }
+
+    let tag_attributes = match &options.tag_attributes {
+      Some(map) => map
+        .iter()
+        .map(|(tag, attrs)| {
+          (
+            tag.to_ascii_lowercase(),
+            attrs.iter().map(|a| a.to_ascii_lowercase()).collect(),
+          )
+        })
+        .collect(),
+      None => DEFAULT_TAG_ATTRIBUTES
+        .iter()
+        .map(|(tag, attrs)| {
+          (
+            tag.to_string(),
+            attrs.iter().map(|a| a.to_string()).collect(),
+          )
+        })
+        .collect(),
+    };
+
+    Self {
+      tags: to_set(&options.tags, DEFAULT_TAGS),
+      generic_attributes: to_set(&options.generic_attributes, DEFAULT_GENERIC_ATTRIBUTES),
+      tag_attributes,
+      url_schemes: to_set(&options.url_schemes, DEFAULT_URL_SCHEMES),
+      iframe_hosts: to_set(&options.iframe_hosts, DEFAULT_IFRAME_HOSTS),
+    }
+  }
+
+  fn allows_attribute(&self, tag: &str, attribute: &str) -> bool {
+    self.generic_attributes.contains(attribute)
+      || GENERIC_ATTRIBUTE_PREFIXES
+        .iter()
+        .any(|prefix| attribute.starts_with(prefix))
+      || self
+        .tag_attributes
+        .get(tag)
+        .is_some_and(|attrs| attrs.contains(attribute))
+  }
+
+  /// Same check as ammonia does on URL attributes before `attribute_filter` sees them.
+  fn allows_url(&self, url: &str) -> bool {
+    match Url::parse(url) {
+      Ok(url) => self.url_schemes.contains(url.scheme()),
+      Err(url::ParseError::RelativeUrlWithoutBase) => true,
+      Err(_) => false,
+    }
+  }
+
+  fn allows_iframe(&self, src: &str) -> bool {
+    Url::parse(src).is_ok_and(|url| {
+      matches!(url.scheme(), "http" | "https")
+        && url
+          .host_str()
+          .is_some_and(|host| self.iframe_hosts.contains(host))
+    })
+  }
+
+  /// Called by ammonia for every attribute of a kept tag. Returns `None` to remove it.
+  fn filter_attribute<'u>(
+    &self,
+    tag: &str,
+    attribute: &str,
+    value: &'u str,
+    stripped: &mut Vec<Stripped>,
+  ) -> Option<Cow<'u, str>> {
+    if !self.allows_attribute(tag, attribute) {
+      stripped.push(Stripped::Attribute {
+        tag: tag.to_string(),
+        attribute: attribute.to_string(),
+      });
+      return None;
+    }
+    if tag == "iframe" && attribute == "src" && !self.allows_iframe(value) {
+      stripped.push(Stripped::Url {
+        tag: tag.to_string(),
+        attribute: attribute.to_string(),
+        url: value.to_string(),
+      });
+      return None;
+    }
+    if attribute == "style" {
+      return filter_style(tag, value, stripped);
+    }
+    Some(Cow::Borrowed(value))
+  }
+
+  fn builder(self: &Arc<Self>, stripped: Arc<Mutex<Vec<Stripped>>>) -> ammonia::Builder<'_> {
+    let allowlist = Arc::clone(self);
+    let mut builder = ammonia::Builder::default();
+    builder
+      .tags(self.tags.iter().map(String::as_str).collect())
+      // every attribute reaches `attribute_filter`, which applies the allowlist and reports
+      .generic_attribute_prefixes(HashSet::from([""]))
+      .url_schemes(self.url_schemes.iter().map(String::as_str).collect())
+      .attribute_filter(move |tag, attribute, value| {
+        allowlist.filter_attribute(tag, attribute, value, &mut stripped.lock().unwrap())
+      })
+      .filter_style_properties(STYLE_PROPERTIES.iter().copied().collect())
+      // `external_link` takes care of `rel`
+      .link_rel(None);
+    builder
+  }
+}
+
+/// Keeps the allowed declarations of a `style` attribute.
+fn filter_style<'u>(
+  tag: &str,
+  value: &'u str,
+  stripped: &mut Vec<Stripped>,
+) -> Option<Cow<'u, str>> {
+  let mut kept = Vec::new();
+  for declaration in value.split(';').map(str::trim).filter(|d| !d.is_empty()) {
+    let property = declaration
+      .split(':')
+      .next()
+      .unwrap_or_default()
+      .trim()
+      .to_ascii_lowercase();
+    if STYLE_PROPERTIES.contains(&property.as_str()) {
+      kept.push(declaration);
+    } else {
+      stripped.push(Stripped::Style {
+        tag: tag.to_string(),
+        property,
+      });
+    }
+  }
+  if kept.is_empty() {
+    None
+  } else {
+    Some(Cow::Owned(kept.join(";")))
+  }
+}
+
+/// Sanitizes rendered HTML with the allowlist and returns it together with what was removed.
+///
+/// # Examples
+///
+/// ```
+/// // Input:
+/// // <p onclick="alert(1)">Hi</p><script>alert(1)</script>
+///
+/// // Output:
+/// // <p>Hi</p>
+/// // stripped: [Tag { tag: "script" }, Attribute { tag: "p", attribute: "onclick" }]
+/// ```
+pub fn sanitize(html: &str, options: &SanitizeOptions) -> (String, Vec<Stripped>) {
+  let allowlist = Arc::new(Allowlist::new(options));
+  let mut stripped = removed_before_filter(html, &allowlist);
+  let filtered = Arc::new(Mutex::new(Vec::new()));
+  let sanitized = allowlist
+    .builder(Arc::clone(&filtered))
+    .clean(html)
+    .to_string();
+  stripped.append(&mut filtered.lock().unwrap());
+  (sanitized, stripped)
+}
+
+/// Reports what ammonia removes before `attribute_filter` is called: tags that are not allowed
+/// and URLs failing the scheme check. Tokenizes with html5ever like ammonia, so entities in
+/// attribute values are decoded the same way.
+fn removed_before_filter(html: &str, allowlist: &Allowlist) -> Vec<Stripped> {
+  let input = BufferQueue::default();
+  input.push_back(StrTendril::from_slice(html));
+  let tokenizer = Tokenizer::new(
+    StartTagSink {
+      allowlist,
+      stripped: RefCell::new(Vec::new()),
+    },
+    Default::default(),
+  );
+  let _ = tokenizer.feed(&input);
+  tokenizer.end();
+  tokenizer.sink.stripped.take()
+}
+
+struct StartTagSink<'a> {
+  allowlist: &'a Allowlist,
+  stripped: RefCell<Vec<Stripped>>,
+}
+
+impl TokenSink for StartTagSink<'_> {
+  type Handle = ();
+
+  fn process_token(&self, token: Token, _line_number: u64) -> TokenSinkResult<()> {
+    let Token::TagToken(tag) = token else {
+      return TokenSinkResult::Continue;
+    };
+    if tag.kind != TagKind::StartTag {
+      return TokenSinkResult::Continue;
+    }
+
+    let name = tag.name.to_string();
+    let mut stripped = self.stripped.borrow_mut();
+    if !self.allowlist.tags.contains(&name) {
+      stripped.push(Stripped::Tag { tag: name });
+    } else {
+      for attr in &tag.attrs {
+        let attribute = attr.name.local.to_string();
+        if !is_url_attribute(&name, &attribute) || self.allowlist.allows_url(&attr.value) {
+          continue;
+        }
+        if self.allowlist.allows_attribute(&name, &attribute) {
+          stripped.push(Stripped::Url {
+            tag: name.clone(),
+            attribute,
+            url: attr.value.to_string(),
+          });
+        } else {
+          stripped.push(Stripped::Attribute {
+            tag: name.clone(),
+            attribute,
+          });
+        }
+      }
+    }
+
+    // the tree builder switches the tokenizer state for these, so their contents are not markup
+    match &*tag.name {
+      "script" => TokenSinkResult::RawData(RawKind::ScriptData),
+      "style" | "xmp" | "iframe" | "noembed" | "noframes" | "noscript" => {
+        TokenSinkResult::RawData(RawKind::Rawtext)
+      }
+      "textarea" | "title" => TokenSinkResult::RawData(RawKind::Rcdata),
+      "plaintext" => TokenSinkResult::Plaintext,
+      _ => TokenSinkResult::Continue,
+    }
+  }
+}
+
+/// Attributes ammonia checks against the URL schemes.
+fn is_url_attribute(tag: &str, attribute: &str) -> bool {
+  matches!(attribute, "href" | "xlink:href" | "src")
+    || matches!(
+      (tag, attribute),
+      ("form", "action")
+        | ("object", "data")
+        | ("button" | "input", "formaction")
+        | ("a", "ping")
+        | ("video", "poster")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_sanitize_script() {
+    let (html, stripped) = sanitize(
+      "<p>Hi</p><script>alert('<b>')</script>",
+      &SanitizeOptions::default(),
+    );
+    assert_eq!(html, "<p>Hi</p>");
+    assert_eq!(
+      stripped,
+      vec![Stripped::Tag {
+        tag: "script".to_string()
+      }]
+    );
+  }
+
+  #[test]
+  fn test_sanitize_event_handler() {
+    let (html, stripped) = sanitize(
+      "<img src=\"a.png\" alt=\"a\" onerror=\"alert(1)\">",
+      &SanitizeOptions::default(),
+    );
+    assert_eq!(html, "<img src=\"a.png\" alt=\"a\">");
+    assert_eq!(
+      stripped,
+      vec![Stripped::Attribute {
+        tag: "img".to_string(),
+        attribute: "onerror".to_string()
+      }]
+    );
+  }
+
+  #[test]
+  fn test_sanitize_url_scheme() {
+    let (html, stripped) = sanitize(
+      "<a href=\"javascript:alert(1)\">x</a><a href=\"/about\">about</a>",
+      &SanitizeOptions::default(),
+    );
+    assert_eq!(html, "<a>x</a><a href=\"/about\">about</a>");
+    assert_eq!(
+      stripped,
+      vec![Stripped::Url {
+        tag: "a".to_string(),
+        attribute: "href".to_string(),
+        url: "javascript:alert(1)".to_string()
+      }]
+    );
+  }
+
+  #[test]
+  fn test_sanitize_keeps_rendered_markdown() {
+    let input = "<figure class=\"highlight js\"><table><tr><td class=\"gutter\"><pre><span class=\"line\">1</span><br></pre></td></tr></table></figure>\n<table>\n<thead>\n<tr>\n<th style=\"text-align:right\">a</th>\n</tr>\n</thead>\n</table>\n<p><math xmlns=\"http://www.w3.org/1998/Math/MathML\" display=\"inline\"><mi>x</mi></math><a href=\"https://example.com\" target=\"_blank\" rel=\"noopener\">x</a></p>\n";
+    let (html, stripped) = sanitize(input, &SanitizeOptions::default());
+
+    assert!(stripped.is_empty(), "{:?}", stripped);
+    assert!(html.contains("<td class=\"gutter\">"));
+    assert!(html.contains("<th style=\"text-align:right\">"));
+    assert!(html.contains("display=\"inline\"><mi>x</mi></math>"));
+    assert!(html.contains("target=\"_blank\" rel=\"noopener\""));
+  }
+
+  #[test]
+  fn test_sanitize_keeps_highlighted_code() {
+    let input = crate::highlight::highlight(
+      "let a = 1;\nlet b = 2;",
+      crate::highlight::HighlightOptions {
+        lang: Some("rust".to_string()),
+        first_line: 3,
+        mark_lines: Some("4".to_string()),
+        layout: crate::highlight::HighlightLayout::Pre,
+        copy_button: true,
+        language_label: true,
+        ..Default::default()
+      },
+    );
+    let (html, stripped) = sanitize(&input, &SanitizeOptions::default());
+
+    assert!(stripped.is_empty(), "{:?}", stripped);
+    assert_eq!(html, input);
+  }
+
+  #[test]
+  fn test_sanitize_custom_allowlist() {
+    let options = SanitizeOptions {
+      tags: Some(vec!["p".to_string()]),
+      generic_attributes: Some(vec![]),
+      ..Default::default()
+    };
+    let (html, stripped) = sanitize("<p class=\"a\"><em>x</em></p>", &options);
+    assert_eq!(html, "<p>x</p>");
+    assert_eq!(
+      stripped,
+      vec![
+        Stripped::Tag {
+          tag: "em".to_string()
+        },
+        Stripped::Attribute {
+          tag: "p".to_string(),
+          attribute: "class".to_string()
+        }
+      ]
+    );
+  }
+
+  #[test]
+  fn test_sanitize_ignores_comments() {
+    let (html, stripped) = sanitize("<!-- <script> -->text", &SanitizeOptions::default());
+    assert_eq!(html, "text");
+    assert!(stripped.is_empty());
+  }
+
+  #[test]
+  fn test_sanitize_encoded_url_scheme() {
+    let (html, stripped) = sanitize(
+      "<a href=\"jav&#x61;script:alert(1)\">x</a>",
+      &SanitizeOptions::default(),
+    );
+    assert_eq!(html, "<a>x</a>");
+    assert_eq!(
+      stripped,
+      vec![Stripped::Url {
+        tag: "a".to_string(),
+        attribute: "href".to_string(),
+        url: "javascript:alert(1)".to_string()
+      }]
+    );
+  }
+
+  #[test]
+  fn test_sanitize_style_properties() {
+    let (html, stripped) = sanitize(
+      "<table><tr><td style=\"text-align:right; color:red\">a</td><td style=\"position:fixed\">b</td></tr></table>",
+      &SanitizeOptions::default(),
+    );
+    assert_eq!(
+      html,
+      "<table><tbody><tr><td style=\"text-align:right\">a</td><td>b</td></tr></tbody></table>"
+    );
+    assert_eq!(
+      stripped,
+      vec![
+        Stripped::Style {
+          tag: "td".to_string(),
+          property: "color".to_string()
+        },
+        Stripped::Style {
+          tag: "td".to_string(),
+          property: "position".to_string()
+        }
+      ]
+    );
+  }
+
+  #[test]
+  fn test_sanitize_iframe_hosts() {
+    let (html, stripped) = sanitize(
+      "<iframe src=\"https://www.youtube.com/embed/abc\"></iframe><iframe src=\"https://example.com/\"></iframe>",
+      &SanitizeOptions::default(),
+    );
+    assert_eq!(
+      html,
+      "<iframe src=\"https://www.youtube.com/embed/abc\"></iframe><iframe></iframe>"
+    );
+    assert_eq!(
+      stripped,
+      vec![Stripped::Url {
+        tag: "iframe".to_string(),
+        attribute: "src".to_string(),
+        url: "https://example.com/".to_string()
+      }]
+    );
+  }
+}
@@ -0,0 +1,761 @@
+use std::collections::HashMap;
+use std::ops::Range;
+use std::sync::LazyLock;
+
+use markdown_it::common::utils::escape_html;
+
+/// Hexo tag plugin expansion (`{% name args %}` and `{% name %}body{% endname %}`).
+///
+/// Tags are expanded before the markdown is rendered. Tags inside fenced code blocks are
+/// left as-is, and unknown tags are reported as errors instead of leaking into the HTML.
+///
+/// See: https://hexo.io/docs/tag-plugins
+#[derive(Debug, Default, Clone)]
+pub struct TagContext {
+  /// Formatted path of the content being rendered (e.g. `/articles/2024/01/example/`).
+  pub post_path: String,
+  /// Link targets for `post_link`, keyed by post slug.
+  pub posts: HashMap<String, PostRef>,
+}
+
+#[derive(Debug, Clone)]
+pub struct PostRef {
+  pub path: String,
+  pub title: String,
+}
+
+#[derive(Debug)]
+pub struct Tag<'a> {
+  pub name: &'a str,
+  pub args: Vec<String>,
+  /// Content between the opening and closing tags. `None` for inline tags.
+  pub body: Option<String>,
+}
+
+pub type TagHandler = fn(&Tag, &TagContext) -> Result<String, String>;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TagKind {
+  /// `{% name args %}`
+  Inline,
+  /// `{% name args %}body{% endname %}`, tags in the body are expanded first.
+  Block,
+  /// Same as `Block`, but the body is passed through without expanding tags.
+  RawBlock,
+}
+
+#[derive(Debug, Default)]
+pub struct TagRegistry {
+  handlers: HashMap<String, (TagKind, TagHandler)>,
+}
+
+static BUILTIN_REGISTRY: LazyLock<TagRegistry> = LazyLock::new(TagRegistry::with_builtins);
+
+impl TagRegistry {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Creates a registry with the built-in tags: `raw`, `asset_img`, `post_link`, `codeblock`, `blockquote` and `youtube`.
+  pub fn with_builtins() -> Self {
+    let mut registry = Self::new();
+    registry.register("raw", TagKind::RawBlock, builtins::raw);
+    registry.register("asset_img", TagKind::Inline, builtins::asset_img);
+    registry.register("post_link", TagKind::Inline, builtins::post_link);
+    registry.register("codeblock", TagKind::RawBlock, builtins::codeblock);
+    registry.register("blockquote", TagKind::Block, builtins::blockquote);
+    registry.register("youtube", TagKind::Inline, builtins::youtube);
+    registry
+  }
+
+  pub fn register(&mut self, name: &str, kind: TagKind, handler: TagHandler) {
+    self.handlers.insert(name.to_string(), (kind, handler));
+  }
+
+  /// Expands all tags in `text`.
+  ///
+  /// All problems found in the document are reported at once, one per line.
+  pub fn expand(&self, text: &str, ctx: &TagContext) -> Result<String, String> {
+    let mut errors = Vec::new();
+    let fences = fenced_ranges(text);
+    let result = self.expand_range(text, 0..text.len(), &fences, ctx, &mut errors);
+    if errors.is_empty() {
+      Ok(result)
+    } else {
+      Err(errors.join("\n"))
+    }
+  }
+
+  fn expand_range(
+    &self,
+    source: &str,
+    range: Range<usize>,
+    fences: &[Range<usize>],
+    ctx: &TagContext,
+    errors: &mut Vec<String>,
+  ) -> String {
+    let end = range.end;
+    let mut result = String::with_capacity(range.len());
+    let mut pos = range.start;
+
+    while let Some(open) = find_tag(source, pos, end) {
+      if fences.iter().any(|r| r.contains(&open.start)) {
+        result.push_str(&source[pos..open.end]);
+        pos = open.end;
+        continue;
+      }
+
+      result.push_str(&source[pos..open.start]);
+      pos = open.end;
+      let line = line_number(source, open.start);
+
+      let Some(name) = open.name() else {
+        errors.push(format!("line {}: empty tag", line));
+        continue;
+      };
+
+      let Some(&(kind, handler)) = self.handlers.get(name) else {
+        if name.starts_with("end") && self.handlers.contains_key(&name[3..]) {
+          errors.push(format!("line {}: unexpected `{}`", line, name));
+        } else {
+          errors.push(format!("line {}: unknown tag plugin `{}`", line, name));
+        }
+        continue;
+      };
+
+      let body = match kind {
+        TagKind::Inline => None,
+        TagKind::Block | TagKind::RawBlock => {
+          let Some(close) = find_closing_tag(source, pos, end, name) else {
+            errors.push(format!("line {}: `{}` is not closed", line, name));
+            continue;
+          };
+          let body = if kind == TagKind::Block {
+            self.expand_range(source, pos..close.start, fences, ctx, errors)
+          } else {
+            source[pos..close.start].to_string()
+          };
+          pos = close.end;
+          Some(body)
+        }
+      };
+
+      let tag = Tag {
+        name,
+        args: split_args(open.args()),
+        body,
+      };
+      match handler(&tag, ctx) {
+        Ok(s) => result.push_str(&s),
+        Err(e) => errors.push(format!("line {}: {}: {}", line, tag.name, e)),
+      }
+    }
+
+    if let Some(start) = find_unterminated(source, pos, end, fences) {
+      errors.push(format!(
+        "line {}: `{{%` is not closed with `%}}`",
+        line_number(source, start)
+      ));
+    }
+
+    result.push_str(&source[pos..end]);
+    result
+  }
+
+  /// Returns byte offsets and names of block tags without `{% end<name> %}`, outside code fences.
+  /// A trailing `{%` without `%}` is reported with an empty name.
+  pub fn find_unclosed(&self, text: &str) -> Vec<(usize, String)> {
+    let fences = fenced_ranges(text);
+    let mut unclosed = Vec::new();
+    let mut pos = 0;
+
+    while let Some(open) = find_tag(text, pos, text.len()) {
+      pos = open.end;
+      if fences.iter().any(|r| r.contains(&open.start)) {
+        continue;
+      }
+      let Some(name) = open.name() else {
+        continue;
+      };
+      let Some(&(kind, _)) = self.handlers.get(name) else {
+        continue;
+      };
+      if kind == TagKind::Inline {
+        continue;
+      }
+      match find_closing_tag(text, pos, text.len(), name) {
+        // tags in raw bodies aren't tags
+        Some(close) if kind == TagKind::RawBlock => pos = close.end,
+        Some(_) => {}
+        None => unclosed.push((open.start, name.to_string())),
+      }
+    }
+
+    if let Some(start) = find_unterminated(text, pos, text.len(), &fences) {
+      unclosed.push((start, String::new()));
+    }
+    unclosed
+  }
+}
+
+/// Expands tags with the built-in registry.
+pub fn expand(text: &str, ctx: &TagContext) -> Result<String, String> {
+  BUILTIN_REGISTRY.expand(text, ctx)
+}
+
+/// Finds unclosed tags with the built-in registry, see `TagRegistry::find_unclosed`.
+pub fn find_unclosed(text: &str) -> Vec<(usize, String)> {
+  BUILTIN_REGISTRY.find_unclosed(text)
+}
+
+struct TagMatch<'a> {
+  start: usize,
+  end: usize,
+  inner: &'a str,
+}
+
+impl<'a> TagMatch<'a> {
+  fn name(&self) -> Option<&'a str> {
+    self.inner.split_whitespace().next()
+  }
+
+  fn args(&self) -> &'a str {
+    let inner = self.inner.trim_start();
+    match inner.find(char::is_whitespace) {
+      Some(i) => &inner[i..],
+      None => "",
+    }
+  }
+}
+
+fn find_tag(source: &str, from: usize, end: usize) -> Option<TagMatch<'_>> {
+  let start = from + source[from..end].find("{%")?;
+  let close = start + 2 + source[start + 2..end].find("%}")?;
+  Some(TagMatch {
+    start,
+    end: close + 2,
+    inner: &source[start + 2..close],
+  })
+}
+
+/// Finds a `{%` without `%}`, where `find_tag` stops, outside code fences.
+fn find_unterminated(
+  source: &str,
+  from: usize,
+  end: usize,
+  fences: &[Range<usize>],
+) -> Option<usize> {
+  source[from..end]
+    .match_indices("{%")
+    .map(|(i, _)| from + i)
+    .find(|start| !fences.iter().any(|r| r.contains(start)))
+}
+
+/// Finds `{% end<name> %}`, skipping nested tags with the same name.
+fn find_closing_tag<'a>(
+  source: &'a str,
+  from: usize,
+  end: usize,
+  name: &str,
+) -> Option<TagMatch<'a>> {
+  let end_name = format!("end{}", name);
+  let mut depth = 0;
+  let mut pos = from;
+
+  while let Some(tag) = find_tag(source, pos, end) {
+    pos = tag.end;
+    match tag.name() {
+      Some(n) if n == name => depth += 1,
+      Some(n) if n == end_name => {
+        if depth == 0 {
+          return Some(tag);
+        }
+        depth -= 1;
+      }
+      _ => {}
+    }
+  }
+  None
+}
+
+/// Splits arguments on whitespace. Single or double quotes group words into one argument.
+fn split_args(args: &str) -> Vec<String> {
+  let mut result = Vec::new();
+  let mut current = String::new();
+  let mut quote: Option<char> = None;
+  let mut in_arg = false;
+
+  for c in args.chars() {
+    match quote {
+      Some(q) if c == q => quote = None,
+      Some(_) => current.push(c),
+      None if c == '"' || c == '\'' => {
+        quote = Some(c);
+        in_arg = true;
+      }
+      None if c.is_whitespace() => {
+        if in_arg {
+          result.push(std::mem::take(&mut current));
+          in_arg = false;
+        }
+      }
+      None => {
+        current.push(c);
+        in_arg = true;
+      }
+    }
+  }
+  if in_arg {
+    result.push(current);
+  }
+
+  result
+}
+
+/// Byte ranges of fenced code blocks (` ``` ` or `~~~`), including blockquoted ones.
+fn fenced_ranges(text: &str) -> Vec<Range<usize>> {
+  let mut ranges = Vec::new();
+  let mut open: Option<(usize, char, usize)> = None;
+  let mut offset = 0;
+
+  for line in text.split_inclusive('\n') {
+    let trimmed = line.trim_start_matches([' ', '\t', '>']);
+    let marker = trimmed.chars().next().filter(|c| *c == '`' || *c == '~');
+    let len = marker.map_or(0, |m| trimmed.chars().take_while(|c| *c == m).count());
+
+    match (open, marker) {
+      (None, Some(m)) if len >= 3 => open = Some((offset, m, len)),
+      (Some((start, m, open_len)), Some(c))
+        if c == m && len >= open_len && trimmed[len..].trim().is_empty() =>
+      {
+        ranges.push(start..offset + line.len());
+        open = None;
+      }
+      _ => {}
+    }
+    offset += line.len();
+  }
+  if let Some((start, _, _)) = open {
+    ranges.push(start..text.len());
+  }
+
+  ranges
+}
+
+fn line_number(text: &str, pos: usize) -> usize {
+  text[..pos].matches('\n').count() + 1
+}
+
+mod builtins {
+  use super::*;
+
+  /// `{% raw %}content{% endraw %}`
+  pub fn raw(tag: &Tag, _: &TagContext) -> Result<String, String> {
+    Ok(tag.body.clone().unwrap_or_default())
+  }
+
+  /// `{% asset_img [class names] slug [width] [height] [title text [alt text]] %}`
+  pub fn asset_img(tag: &Tag, ctx: &TagContext) -> Result<String, String> {
+    let slug_index = tag
+      .args
+      .iter()
+      .position(|a| a.contains('.') || a.contains('/'))
+      .ok_or("image slug is required")?;
+
+    let classes = &tag.args[..slug_index];
+    let slug = tag.args[slug_index].trim_start_matches('/');
+    let mut rest = tag.args[slug_index + 1..].iter().peekable();
+
+    let mut attrs = vec![("src", format!("{}{}", ctx.post_path, slug))];
+    if !classes.is_empty() {
+      attrs.push(("class", classes.join(" ")));
+    }
+    for name in ["width", "height"] {
+      if let Some(size) = rest.next_if(|a| a.parse::<u32>().is_ok()) {
+        attrs.push((name, size.clone()));
+      }
+    }
+    if let Some(title) = rest.next() {
+      attrs.push(("title", title.clone()));
+      attrs.push(("alt", rest.next().unwrap_or(title).clone()));
+    }
+
+    Ok(html_tag("img", &attrs, None))
+  }
+
+  /// `{% post_link slug [title] [escape] %}`
+  pub fn post_link(tag: &Tag, ctx: &TagContext) -> Result<String, String> {
+    let slug = tag.args.first().ok_or("post slug is required")?;
+    let post = ctx
+      .posts
+      .get(slug)
+      .ok_or_else(|| format!("post not found: {}", slug))?;
+
+    let mut args = tag.args[1..].to_vec();
+    let escape = match args.last().map(|s| s.as_str()) {
+      Some("true") => {
+        args.pop();
+        true
+      }
+      Some("false") => {
+        args.pop();
+        false
+      }
+      _ => true,
+    };
+    let title = if args.is_empty() {
+      post.title.clone()
+    } else {
+      args.join(" ")
+    };
+    let text = if escape {
+      escape_html(&title).to_string()
+    } else {
+      title.clone()
+    };
+
+    Ok(html_tag(
+      "a",
+      &[("href", post.path.clone()), ("title", title)],
+      Some(&text),
+    ))
+  }
+
+  /// `{% codeblock [title] [lang:language] [mark:1,4-6] [url] [link text] %}code{% endcodeblock %}`
+  ///
+  /// Expands to a fenced code block so it is highlighted like any other fence.
+  pub fn codeblock(tag: &Tag, _: &TagContext) -> Result<String, String> {
+    let mut lang = None;
+    let mut mark = None;
+    let mut url = None;
+    let mut title = Vec::new();
+    let mut link_text = Vec::new();
+
+    for arg in tag.args.iter() {
+      if let Some(l) = arg.strip_prefix("lang:") {
+        lang = Some(l.to_string());
+      } else if let Some(m) = arg.strip_prefix("mark:") {
+        mark = Some(m.to_string());
+      } else if url.is_none() && is_url(arg) {
+        url = Some(arg.clone());
+      } else if url.is_some() {
+        link_text.push(arg.as_str());
+      } else {
+        title.push(arg.as_str());
+      }
+    }
+
+    let body = tag.body.as_deref().unwrap_or_default();
+    let body = body.strip_prefix('\n').unwrap_or(body);
+    let body = body.strip_suffix('\n').unwrap_or(body);
+
+    let mut info = Vec::new();
+    if lang.is_some() || mark.is_some() || !title.is_empty() {
+      info.push(lang.unwrap_or_else(|| "plaintext".to_string()));
+    }
+    if let Some(mark) = mark {
+      info.push(format!("{{{}}}", mark));
+    }
+    if !title.is_empty() {
+      info.push(title.join(" "));
+      if let Some(url) = url {
+        info.push(url);
+        if !link_text.is_empty() {
+          info.push(link_text.join(" "));
+        }
+      }
+    }
+
+    let longest_run = body
+      .split(|c| c != '`')
+      .map(|run| run.len())
+      .max()
+      .unwrap_or(0);
+    let fence = "`".repeat(longest_run.max(2) + 1);
+    Ok(format!("{}{}\n{}\n{}", fence, info.join(" "), body, fence))
+  }
+
+  /// `{% blockquote [author[, source]] [link] [source_link_title] %}content{% endblockquote %}`
+  ///
+  /// Expands to a markdown blockquote followed by a `<footer>` with the citation.
+  pub fn blockquote(tag: &Tag, _: &TagContext) -> Result<String, String> {
+    let link_index = tag.args.iter().position(|a| is_url(a));
+    let (author_args, link, link_title) = match link_index {
+      Some(i) => (
+        &tag.args[..i],
+        Some(tag.args[i].as_str()),
+        tag.args[i + 1..].join(" "),
+      ),
+      None => (&tag.args[..], None, String::new()),
+    };
+
+    let author_line = author_args.join(" ");
+    let (author, source) = match author_line.split_once(',') {
+      Some((a, s)) => (a.trim().to_string(), s.trim().to_string()),
+      None => (author_line.trim().to_string(), String::new()),
+    };
+
+    let cite = match link {
+      Some(link) => {
+        let text = if !link_title.is_empty() {
+          link_title
+        } else if !source.is_empty() {
+          source
+        } else {
+          link.to_string()
+        };
+        html_tag(
+          "a",
+          &[("href", link.to_string())],
+          Some(&escape_html(&text)),
+        )
+      }
+      None => escape_html(&source).to_string(),
+    };
+
+    let mut footer = String::new();
+    if !author.is_empty() {
+      footer.push_str(&html_tag("strong", &[], Some(&escape_html(&author))));
+    }
+    if !cite.is_empty() {
+      footer.push_str(&html_tag("cite", &[], Some(&cite)));
+    }
+
+    let body = tag.body.as_deref().unwrap_or_default().trim_matches('\n');
+    let mut lines: Vec<String> = body
+      .lines()
+      .map(|line| {
+        if line.is_empty() {
+          ">".to_string()
+        } else {
+          format!("> {}", line)
+        }
+      })
+      .collect();
+    if !footer.is_empty() {
+      lines.push(">".to_string());
+      lines.push(format!("> {}", html_tag("footer", &[], Some(&footer))));
+    }
+
+    Ok(lines.join("\n"))
+  }
+
+  /// `{% youtube video_id [type] [cookie] %}`
+  pub fn youtube(tag: &Tag, _: &TagContext) -> Result<String, String> {
+    let id = tag.args.first().ok_or("video id is required")?;
+    let is_playlist = tag.args.get(1).is_some_and(|a| a == "playlist");
+    let cookie = !tag.args.iter().skip(1).any(|a| a == "false");
+
+    let host = if cookie {
+      "www.youtube.com"
+    } else {
+      "www.youtube-nocookie.com"
+    };
+    let src = if is_playlist {
+      format!("https://{}/embed/videoseries?list={}", host, id)
+    } else {
+      format!("https://{}/embed/{}", host, id)
+    };
+
+    let iframe = format!(
+      "<iframe src=\"{}\" frameborder=\"0\" loading=\"lazy\" allowfullscreen></iframe>",
+      escape_html(&src)
+    );
+    Ok(html_tag(
+      "div",
+      &[("class", "video-container".to_string())],
+      Some(&iframe),
+    ))
+  }
+
+  fn is_url(s: &str) -> bool {
+    s.starts_with("http://") || s.starts_with("https://") || s.starts_with("//")
+  }
+
+  fn html_tag(name: &str, attrs: &[(&str, String)], content: Option<&str>) -> String {
+    let mut s = format!("<{}", name);
+    for (k, v) in attrs {
+      s.push_str(&format!(" {}=\"{}\"", k, escape_html(v)));
+    }
+    s.push('>');
+    if let Some(content) = content {
+      s.push_str(content);
+      s.push_str(&format!("</{}>", name));
+    }
+    s
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn ctx() -> TagContext {
+    let mut posts = HashMap::new();
+    posts.insert(
+      "hello-world".to_string(),
+      PostRef {
+        path: "/articles/2024/01/hello-world/".to_string(),
+        title: "Hello <World>".to_string(),
+      },
+    );
+    TagContext {
+      post_path: "/articles/2024/02/example/".to_string(),
+      posts,
+    }
+  }
+
+  #[test]
+  fn test_split_args() {
+    assert_eq!(split_args(" a b  c "), vec!["a", "b", "c"]);
+    assert_eq!(
+      split_args(r#"img.png "a title" 'alt text'"#),
+      vec!["img.png", "a title", "alt text"]
+    );
+    assert_eq!(split_args(r#"a "" b"#), vec!["a", "", "b"]);
+    assert!(split_args("").is_empty());
+  }
+
+  #[test]
+  fn test_no_tags() {
+    assert_eq!(expand("Hello World", &ctx()).unwrap(), "Hello World");
+    assert_eq!(expand("", &ctx()).unwrap(), "");
+  }
+
+  #[test]
+  fn test_raw() {
+    let input = "{% raw %}{{ value }} {% unknown %}{% endraw %}";
+    assert_eq!(expand(input, &ctx()).unwrap(), "{{ value }} {% unknown %}");
+  }
+
+  #[test]
+  fn test_asset_img() {
+    assert_eq!(
+      expand("{% asset_img example.jpg %}", &ctx()).unwrap(),
+      "<img src=\"/articles/2024/02/example/example.jpg\">"
+    );
+    assert_eq!(
+      expand(
+        r#"{% asset_img left example.jpg 300 200 "A title" "An alt" %}"#,
+        &ctx()
+      )
+      .unwrap(),
+      "<img src=\"/articles/2024/02/example/example.jpg\" class=\"left\" width=\"300\" height=\"200\" title=\"A title\" alt=\"An alt\">"
+    );
+  }
+
+  #[test]
+  fn test_post_link() {
+    assert_eq!(
+      expand("{% post_link hello-world %}", &ctx()).unwrap(),
+      "<a href=\"/articles/2024/01/hello-world/\" title=\"Hello &lt;World&gt;\">Hello &lt;World&gt;</a>"
+    );
+    assert_eq!(
+      expand("{% post_link hello-world Read this %}", &ctx()).unwrap(),
+      "<a href=\"/articles/2024/01/hello-world/\" title=\"Read this\">Read this</a>"
+    );
+    assert_eq!(
+      expand("{% post_link missing %}", &ctx()).unwrap_err(),
+      "line 1: post_link: post not found: missing"
+    );
+  }
+
+  #[test]
+  fn test_codeblock() {
+    let input = "{% codeblock Example lang:rust https://example.com Source %}\nfn main() {}\n{% endcodeblock %}";
+    assert_eq!(
+      expand(input, &ctx()).unwrap(),
+      "```rust Example https://example.com Source\nfn main() {}\n```"
+    );
+
+    let input = "{% codeblock lang:rust mark:1,3-4 %}\nfn main() {}\n{% endcodeblock %}";
+    assert_eq!(
+      expand(input, &ctx()).unwrap(),
+      "```rust {1,3-4}\nfn main() {}\n```"
+    );
+
+    let input = "{% codeblock %}\n{% raw %}```{% endraw %}\n{% endcodeblock %}";
+    assert_eq!(
+      expand(input, &ctx()).unwrap(),
+      "````\n{% raw %}```{% endraw %}\n````"
+    );
+  }
+
+  #[test]
+  fn test_blockquote() {
+    let input = "{% blockquote David Levithan, Wide Awake %}\nDo not just seek happiness.\n{% endblockquote %}";
+    assert_eq!(
+      expand(input, &ctx()).unwrap(),
+      "> Do not just seek happiness.\n>\n> <footer><strong>David Levithan</strong><cite>Wide Awake</cite></footer>"
+    );
+
+    let input = "{% blockquote @user https://example.com/status/1 Post %}\nline 1\n\n{% post_link hello-world %}\n{% endblockquote %}";
+    assert_eq!(
+      expand(input, &ctx()).unwrap(),
+      "> line 1\n>\n> <a href=\"/articles/2024/01/hello-world/\" title=\"Hello &lt;World&gt;\">Hello &lt;World&gt;</a>\n>\n> <footer><strong>@user</strong><cite><a href=\"https://example.com/status/1\">Post</a></cite></footer>"
+    );
+  }
+
+  #[test]
+  fn test_youtube() {
+    assert_eq!(
+      expand("{% youtube abc123 %}", &ctx()).unwrap(),
+      "<div class=\"video-container\"><iframe src=\"https://www.youtube.com/embed/abc123\" frameborder=\"0\" loading=\"lazy\" allowfullscreen></iframe></div>"
+    );
+    assert!(
+      expand("{% youtube list1 playlist false %}", &ctx())
+        .unwrap()
+        .contains("https://www.youtube-nocookie.com/embed/videoseries?list=list1")
+    );
+  }
+
+  #[test]
+  fn test_tags_in_code_fence_are_kept() {
+    let input = "```\n{% unknown %}\n```\n\n> ~~~\n> {% youtube abc %}\n> ~~~";
+    assert_eq!(expand(input, &ctx()).unwrap(), input);
+  }
+
+  #[test]
+  fn test_errors() {
+    let input = "line 1\n{% unknown foo %}\n{% blockquote %}\nnot closed\n{% endraw %}";
+    assert_eq!(
+      expand(input, &ctx()).unwrap_err(),
+      "line 2: unknown tag plugin `unknown`\nline 3: `blockquote` is not closed\nline 5: unexpected `endraw`"
+    );
+  }
+
+  #[test]
+  fn test_unterminated_tag() {
+    let input = "{% youtube abc %}\n```\n{% x\n```\ntext {% youtube def";
+    assert_eq!(
+      expand(input, &ctx()).unwrap_err(),
+      "line 5: `{%` is not closed with `%}`"
+    );
+  }
+
+  #[test]
+  fn test_find_unclosed() {
+    let input = "{% blockquote %}\n{% raw %}{% blockquote %}{% endraw %}\n```\n{% raw %}\n```\n{% youtube abc %} {% ";
+    assert_eq!(
+      find_unclosed(input),
+      vec![(0, "blockquote".to_string()), (91, String::new())]
+    );
+    assert!(find_unclosed("{% blockquote %}a{% endblockquote %}").is_empty());
+  }
+
+  #[test]
+  fn test_custom_registry() {
+    fn shout(tag: &Tag, _: &TagContext) -> Result<String, String> {
+      Ok(tag.args.join(" ").to_uppercase())
+    }
+
+    let mut registry = TagRegistry::new();
+    registry.register("shout", TagKind::Inline, shout);
+
+    assert_eq!(
+      registry.expand("{% shout hello world %}!", &ctx()).unwrap(),
+      "HELLO WORLD!"
+    );
+    assert!(registry.expand("{% youtube abc %}", &ctx()).is_err());
+  }
+}
@@ -14,7 +14,7 @@ pub fn format_path(path: &str, content_type: &str) -> String {
   if let Some(index) = p.rfind(".html/") {
     let prefix = &p[..index];
     if let Some(slash_index) = prefix.rfind('/') {
-      p = format!("{}", &prefix[..=slash_index]);
+      p = prefix[..=slash_index].to_string();
     }
   }
 